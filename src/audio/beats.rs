@@ -0,0 +1,322 @@
+use crate::model::time_markers::TimeMarkers;
+
+use super::wav::WavAudio;
+
+//Time between consecutive frames of the onset envelope, in seconds
+const HOP_SECONDS: f32 = 0.01;
+//Range of tempos considered, in beats per minute
+const MIN_BPM: f32 = 60.0;
+const MAX_BPM: f32 = 200.0;
+//Tempo favoured when several candidates fit the music equally well, in beats per minute
+const PREFERRED_BPM: f32 = 120.0;
+//How strongly the beat tracker penalizes beat intervals that differ from the tempo
+const TIGHTNESS: f32 = 100.0;
+//Minimum onset strength above the local mean, in standard deviations of the envelope
+const ONSET_DELTA: f32 = 0.5;
+//Half width of the window used to compute the local mean of the envelope, in frames
+const ONSET_WINDOW: usize = 10;
+//Minimum time between consecutive onsets, in seconds
+const MIN_ONSET_GAP: f32 = 0.05;
+
+/**
+ * Result of the beat analysis of an audio track
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct BeatAnalysis {
+    //Estimated tempo in beats per minute, or 0 if the track is too short or silent
+    pub tempo: f32,
+    //Time of each beat, in seconds
+    pub beats: Vec<f32>,
+    //Time of each note onset, in seconds
+    pub onsets: Vec<f32>,
+    //Strength of the onset envelope at each beat, used to find the first beat of each bar
+    strengths: Vec<f32>,
+}
+
+impl BeatAnalysis {
+    /**
+     * Returns the index of the first beat that starts a bar, i.e. the phase whose beats
+     * have the strongest onsets.
+     */
+    pub fn first_downbeat(&self, beats_per_bar: usize) -> usize {
+        if beats_per_bar == 0 { return 0 }
+
+        (0..beats_per_bar.min(self.beats.len()))
+            .map(|phase| (phase, self.strengths.iter().skip(phase).step_by(beats_per_bar).sum::<f32>()))
+            .fold((0, f32::NEG_INFINITY), |best, current| if current.1 > best.1 { current } else { best })
+            .0
+    }
+
+    /**
+     * Creates time markers for every beat and bar, named "beat_0001", "bar_0001" and so on.
+     * Beats before the first downbeat belong to no bar.
+     *
+     * Parameters:
+     *     beats_per_bar: number of beats of each bar, e.g. 4 for 4/4 time; 0 creates no bar markers
+     */
+    pub fn as_time_markers(&self, beats_per_bar: usize) -> TimeMarkers {
+        let mut markers = TimeMarkers::new();
+        for (index, beat) in self.beats.iter().enumerate() {
+            markers.append(format!("beat_{:04}", index + 1), *beat);
+        }
+
+        if beats_per_bar > 0 {
+            let downbeats = self.beats.iter().skip(self.first_downbeat(beats_per_bar)).step_by(beats_per_bar);
+            for (index, beat) in downbeats.enumerate() {
+                markers.append(format!("bar_{:04}", index + 1), *beat);
+            }
+        }
+
+        markers
+    }
+}
+
+/**
+ * Detects the note onsets, the tempo and the beats of an audio track.
+ *
+ * Onsets are peaks of the increase of the signal energy. The tempo is the beat period with
+ * the strongest autocorrelation of that envelope, favouring tempos close to 120 BPM, and
+ * the beats are tracked with dynamic programming so they fall on strong onsets while
+ * keeping a steady period.
+ */
+pub fn detect_beats(audio: &WavAudio) -> BeatAnalysis {
+    let hop = ((audio.get_sample_rate() as f32 * HOP_SECONDS).round() as usize).max(1);
+    let hop_seconds = hop as f32 / audio.get_sample_rate() as f32;
+    let envelope = onset_envelope(audio.get_samples(), hop);
+    //A rise at frame i of the envelope comes from the last hop of energy frame i + 1
+    let frame_time = |frame: usize| (frame + 2) as f32 * hop_seconds;
+
+    let onsets = pick_onsets(&envelope, (MIN_ONSET_GAP / hop_seconds).ceil() as usize)
+        .into_iter()
+        .map(frame_time)
+        .collect();
+
+    let period = match estimate_period(&envelope, hop_seconds) {
+        Some(period) => period,
+        None => return BeatAnalysis { tempo: 0.0, beats: Vec::new(), onsets, strengths: Vec::new() }
+    };
+    let beat_frames = track_beats(&envelope, period);
+
+    BeatAnalysis {
+        tempo: 60.0 / (period * hop_seconds),
+        beats: beat_frames.iter().map(|frame| frame_time(*frame)).collect(),
+        onsets,
+        strengths: beat_frames.iter().map(|frame| envelope[*frame]).collect(),
+    }
+}
+
+/*
+ * Increase of the log energy of the signal between consecutive frames, normalized by its
+ * standard deviation. Each frame covers two hops.
+ */
+fn onset_envelope(samples: &[f32], hop: usize) -> Vec<f32> {
+    let num_frames = samples.len() / hop;
+    let energy: Vec<f32> = (0..num_frames)
+        .map(|frame| {
+            let window = &samples[frame * hop..((frame + 2) * hop).min(samples.len())];
+            let mean_square = window.iter().map(|x| x * x).sum::<f32>() / window.len() as f32;
+            (1.0 + 1000.0 * mean_square).ln()
+        })
+        .collect();
+
+    let mut envelope: Vec<f32> = energy.windows(2).map(|pair| (pair[1] - pair[0]).max(0.0)).collect();
+    let mean = envelope.iter().sum::<f32>() / envelope.len().max(1) as f32;
+    let deviation = (envelope.iter().map(|x| (x - mean) * (x - mean)).sum::<f32>() / envelope.len().max(1) as f32).sqrt();
+    if deviation > 0.0 {
+        envelope.iter_mut().for_each(|x| *x /= deviation);
+    }
+
+    envelope
+}
+
+/* Frames where the envelope has a local maximum clearly above its local mean */
+fn pick_onsets(envelope: &[f32], min_gap: usize) -> Vec<usize> {
+    let mut onsets: Vec<usize> = Vec::new();
+
+    for (frame, value) in envelope.iter().enumerate() {
+        let window = &envelope[frame.saturating_sub(ONSET_WINDOW)..(frame + ONSET_WINDOW + 1).min(envelope.len())];
+        let local_mean = window.iter().sum::<f32>() / window.len() as f32;
+        let is_peak = window.iter().all(|other| other <= value);
+        let is_far = onsets.last().is_none_or(|last| frame - last >= min_gap);
+
+        if is_peak && *value > local_mean + ONSET_DELTA && is_far {
+            onsets.push(frame);
+        }
+    }
+
+    onsets
+}
+
+/*
+ * Beat period in frames, refined between frames with a parabola through the best lag and
+ * its neighbours, or None if the envelope is too short or flat
+ */
+fn estimate_period(envelope: &[f32], hop_seconds: f32) -> Option<f32> {
+    let min_lag = (60.0 / MAX_BPM / hop_seconds).floor().max(1.0) as usize;
+    let max_lag = ((60.0 / MIN_BPM / hop_seconds).ceil() as usize).min(envelope.len().saturating_sub(1));
+    if min_lag > max_lag { return None }
+
+    let scores: Vec<f32> = (min_lag..=max_lag)
+        .map(|lag| {
+            let correlation = envelope.iter().zip(&envelope[lag..]).map(|(a, b)| a * b).sum::<f32>()
+                / (envelope.len() - lag) as f32;
+            let bpm = 60.0 / (lag as f32 * hop_seconds);
+            correlation * (-0.5 * (bpm / PREFERRED_BPM).log2().powi(2)).exp()
+        })
+        .collect();
+
+    let (index, best) = scores.iter()
+        .enumerate()
+        .fold((0, f32::NEG_INFINITY), |best, (index, score)| if *score > best.1 { (index, *score) } else { best });
+    if best <= 0.0 { return None }
+
+    let mut offset = 0.0;
+    if index > 0 && index + 1 < scores.len() {
+        let (before, after) = (scores[index - 1], scores[index + 1]);
+        let curvature = before - 2.0 * best + after;
+        if curvature < 0.0 { offset = 0.5 * (before - after) / curvature }
+    }
+
+    Some((min_lag + index) as f32 + offset)
+}
+
+/* Frames of the beats that best follow the onsets while keeping the given period */
+fn track_beats(envelope: &[f32], period: f32) -> Vec<usize> {
+    let mut scores: Vec<f32> = Vec::with_capacity(envelope.len());
+    let mut previous: Vec<Option<usize>> = Vec::with_capacity(envelope.len());
+
+    for (frame, strength) in envelope.iter().enumerate() {
+        let earliest = (frame as f32 - 2.0 * period).max(0.0) as usize;
+        let latest = (frame as f32 - period / 2.0).floor();
+
+        let mut best: Option<(usize, f32)> = None;
+        if latest >= 0.0 {
+            for (candidate, candidate_score) in scores.iter().enumerate().take(latest as usize + 1).skip(earliest) {
+                let deviation = ((frame - candidate) as f32 / period).ln();
+                let score = candidate_score - TIGHTNESS * deviation * deviation;
+                if best.is_none_or(|(_, best_score)| score > best_score) {
+                    best = Some((candidate, score));
+                }
+            }
+        }
+
+        match best {
+            Some((candidate, score)) if score > 0.0 => {
+                scores.push(strength + score);
+                previous.push(Some(candidate));
+            },
+            _ => {
+                scores.push(*strength);
+                previous.push(None);
+            }
+        }
+    }
+
+    //The last beat is the best scoring frame within the last period
+    let tail = envelope.len().saturating_sub(period.ceil() as usize);
+    let mut frame = match (tail..envelope.len()).max_by(|a, b| scores[*a].total_cmp(&scores[*b])) {
+        Some(frame) => frame,
+        None => return Vec::new()
+    };
+
+    let mut beats = vec![frame];
+    while let Some(earlier) = previous[frame] {
+        beats.push(earlier);
+        frame = earlier;
+    }
+    beats.reverse();
+
+    beats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 22050;
+
+    /* Short 1 kHz clicks at the given times, over silence */
+    fn click_track(clicks: &[f32], duration: f32) -> WavAudio {
+        let mut samples = vec![0.0; (duration * SAMPLE_RATE as f32) as usize];
+        for click in clicks {
+            let start = (click * SAMPLE_RATE as f32) as usize;
+            for index in 0..SAMPLE_RATE as usize / 50 {
+                let t = index as f32 / SAMPLE_RATE as f32;
+                if let Some(sample) = samples.get_mut(start + index) {
+                    *sample = 0.8 * (-t * 200.0).exp() * (2.0 * std::f32::consts::PI * 1000.0 * t).sin();
+                }
+            }
+        }
+
+        WavAudio::new(SAMPLE_RATE, samples)
+    }
+
+    fn assert_beats(bpm: f32) {
+        let period = 60.0 / bpm;
+        let clicks: Vec<f32> = (0..24).map(|index| 0.5 + index as f32 * period).collect();
+        let analysis = detect_beats(&click_track(&clicks, clicks[23] + 1.0));
+
+        assert!((analysis.tempo - bpm).abs() < 1.5, "{} detected as {}", bpm, analysis.tempo);
+        assert!(analysis.beats.len() >= 22, "{} beats", analysis.beats.len());
+        //Beats may continue the grid of the clicks into the silence around them
+        for beat in &analysis.beats {
+            let error = (((beat - 0.5) / period).round() * period + 0.5 - beat).abs();
+            assert!(error < 0.03, "beat at {} is {} s away from the grid", beat, error);
+        }
+        for onset in &analysis.onsets {
+            let error = clicks.iter().map(|click| (click - onset).abs()).fold(f32::INFINITY, f32::min);
+            assert!(error < 0.03, "onset at {} is {} s away from a click", onset, error);
+        }
+        assert_eq!(analysis.onsets.len(), clicks.len());
+    }
+
+    #[test]
+    fn click_track_at_90_bpm() {
+        assert_beats(90.0);
+    }
+
+    #[test]
+    fn click_track_at_128_bpm() {
+        assert_beats(128.0);
+    }
+
+    #[test]
+    fn silence_has_no_beats() {
+        let analysis = detect_beats(&WavAudio::new(SAMPLE_RATE, vec![0.0; SAMPLE_RATE as usize * 4]));
+        assert_eq!(analysis.tempo, 0.0);
+        assert!(analysis.beats.is_empty() && analysis.onsets.is_empty());
+    }
+
+    #[test]
+    fn short_track_has_no_beats() {
+        let analysis = detect_beats(&WavAudio::new(SAMPLE_RATE, vec![0.5; 100]));
+        assert_eq!(analysis.tempo, 0.0);
+        assert!(analysis.beats.is_empty());
+    }
+
+    #[test]
+    fn non_finite_samples_do_not_panic() {
+        let mut samples = click_track(&[0.5, 1.0, 1.5, 2.0, 2.5, 3.0], 4.0).get_samples().to_vec();
+        samples[SAMPLE_RATE as usize] = f32::NAN;
+        samples[SAMPLE_RATE as usize * 2] = f32::INFINITY;
+
+        detect_beats(&WavAudio::new(SAMPLE_RATE, samples));
+    }
+
+    #[test]
+    fn bar_markers_start_at_the_strongest_beat() {
+        let analysis = BeatAnalysis {
+            tempo: 120.0,
+            beats: vec![0.0, 0.5, 1.0, 1.5, 2.0, 2.5],
+            onsets: Vec::new(),
+            strengths: vec![1.0, 3.0, 1.0, 1.0, 1.0, 3.0],
+        };
+
+        assert_eq!(analysis.first_downbeat(4), 1);
+        let markers = analysis.as_time_markers(4);
+        assert_eq!(markers.get("beat_0006"), Some(2.5));
+        assert_eq!(markers.get("bar_0001"), Some(0.5));
+        assert_eq!(markers.get("bar_0002"), Some(2.5));
+        assert_eq!(markers.get("bar_0003"), None);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod beats;
+pub mod wav;
@@ -0,0 +1,235 @@
+use std::fs;
+use std::path::Path;
+
+//Format tags of the fmt chunk of a WAV file
+const FORMAT_PCM: u16 = 1;
+const FORMAT_IEEE_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/**
+ * Audio track read from a WAV file, mixed down to a single channel.
+ */
+#[derive(Clone)]
+pub struct WavAudio {
+    //Number of samples per second
+    sample_rate: u32,
+    //Mono samples in the range [-1, 1]
+    samples: Vec<f32>
+}
+
+impl WavAudio {
+    pub fn new(sample_rate: u32, samples: Vec<f32>) -> Self {
+        Self { sample_rate, samples }
+    }
+
+    /** Reads a PCM (8, 16, 24 or 32-bit integer) or floating point WAV file */
+    pub fn from_file(path: &Path) -> Result<Self, &'static str> {
+        let bytes = fs::read(path).map_err(|_| "WavAudio.from_file: Can't read the file")?;
+        Self::from_bytes(&bytes)
+    }
+
+    /** Parses the contents of a WAV file. See from_file() for the supported formats. */
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err("WavAudio.from_bytes: The data is not a WAV file");
+        }
+
+        let mut format: Option<(u16, u16, u32, u16)> = None;
+        let mut data: Option<&[u8]> = None;
+        let mut offset = 12;
+
+        while offset + 8 <= bytes.len() {
+            let id = &bytes[offset..offset + 4];
+            let size = read_u32(bytes, offset + 4) as usize;
+            let body = &bytes[offset + 8..(offset + 8 + size).min(bytes.len())];
+
+            if id == b"fmt " {
+                if body.len() < 16 { return Err("WavAudio.from_bytes: The fmt chunk is too short") }
+
+                let mut tag = read_u16(body, 0);
+                if tag == FORMAT_EXTENSIBLE && body.len() >= 26 {
+                    //The sub format GUID starts with the actual format tag
+                    tag = read_u16(body, 24);
+                }
+                format = Some((tag, read_u16(body, 2), read_u32(body, 4), read_u16(body, 14)));
+            } else if id == b"data" {
+                data = Some(body);
+            }
+
+            //Chunks are aligned to two bytes
+            offset += 8 + size + size % 2;
+        }
+
+        let (tag, channels, sample_rate, bits) = format.ok_or("WavAudio.from_bytes: The fmt chunk is missing")?;
+        let data = data.ok_or("WavAudio.from_bytes: The data chunk is missing")?;
+        if channels == 0 || sample_rate == 0 { return Err("WavAudio.from_bytes: Invalid audio format") }
+
+        let width = bits as usize / 8;
+        let decode: fn(&[u8]) -> f32 = match (tag, bits) {
+            (FORMAT_PCM, 8) => |b| (b[0] as f32 - 128.0) / 128.0,
+            (FORMAT_PCM, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+            (FORMAT_PCM, 24) => |b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8388608.0,
+            (FORMAT_PCM, 32) => |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2147483648.0,
+            (FORMAT_IEEE_FLOAT, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            (FORMAT_IEEE_FLOAT, 64) => |b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32,
+            _ => return Err("WavAudio.from_bytes: Unsupported sample format")
+        };
+
+        let samples = data.chunks_exact(width * channels as usize)
+            .map(|frame| frame.chunks_exact(width).map(decode).sum::<f32>() / channels as f32)
+            .collect();
+
+        Ok(Self { sample_rate, samples })
+    }
+
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn get_samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    /** Return the duration of the track in seconds */
+    pub fn duration(&self) -> f32 {
+        self.samples.len() as f32 / self.sample_rate as f32
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend((body.len() as u32).to_le_bytes());
+        bytes.extend(body);
+        if body.len() % 2 == 1 { bytes.push(0) }
+        bytes
+    }
+
+    fn fmt(tag: u16, channels: u16, sample_rate: u32, bits: u16) -> Vec<u8> {
+        let block = channels * bits / 8;
+        let mut body = Vec::new();
+        body.extend(tag.to_le_bytes());
+        body.extend(channels.to_le_bytes());
+        body.extend(sample_rate.to_le_bytes());
+        body.extend((sample_rate * block as u32).to_le_bytes());
+        body.extend(block.to_le_bytes());
+        body.extend(bits.to_le_bytes());
+        chunk(b"fmt ", &body)
+    }
+
+    fn riff(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body: Vec<u8> = chunks.concat();
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend((body.len() as u32 + 4).to_le_bytes());
+        bytes.extend(b"WAVE");
+        bytes.extend(body);
+        bytes
+    }
+
+    fn assert_samples(audio: &WavAudio, expected: &[f32]) {
+        assert_eq!(audio.get_samples().len(), expected.len());
+        for (actual, expected) in audio.get_samples().iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-6, "{:?} != {:?}", audio.get_samples(), expected);
+        }
+    }
+
+    #[test]
+    fn pcm_16() {
+        let data: Vec<u8> = [0i16, 16384, -32768, 32767].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        let audio = WavAudio::from_bytes(&riff(&[fmt(FORMAT_PCM, 1, 8000, 16), chunk(b"data", &data)])).unwrap();
+
+        assert_eq!(audio.get_sample_rate(), 8000);
+        assert_samples(&audio, &[0.0, 0.5, -1.0, 32767.0 / 32768.0]);
+        assert_eq!(audio.duration(), 4.0 / 8000.0);
+    }
+
+    #[test]
+    fn pcm_24() {
+        let data = [0x00, 0x00, 0x40, 0x00, 0x00, 0x80, 0xff, 0xff, 0xff];
+        let audio = WavAudio::from_bytes(&riff(&[fmt(FORMAT_PCM, 1, 44100, 24), chunk(b"data", &data)])).unwrap();
+
+        assert_samples(&audio, &[0.5, -1.0, -1.0 / 8388608.0]);
+    }
+
+    #[test]
+    fn pcm_8() {
+        let audio = WavAudio::from_bytes(&riff(&[fmt(FORMAT_PCM, 1, 8000, 8), chunk(b"data", &[128, 192, 0])])).unwrap();
+
+        assert_samples(&audio, &[0.0, 0.5, -1.0]);
+    }
+
+    #[test]
+    fn float_32_and_64() {
+        let data: Vec<u8> = [0.25f32, -0.75].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        let audio = WavAudio::from_bytes(&riff(&[fmt(FORMAT_IEEE_FLOAT, 1, 48000, 32), chunk(b"data", &data)])).unwrap();
+        assert_samples(&audio, &[0.25, -0.75]);
+
+        let data: Vec<u8> = [0.5f64, -0.125].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        let audio = WavAudio::from_bytes(&riff(&[fmt(FORMAT_IEEE_FLOAT, 1, 48000, 64), chunk(b"data", &data)])).unwrap();
+        assert_samples(&audio, &[0.5, -0.125]);
+    }
+
+    #[test]
+    fn extensible_format() {
+        //The fmt chunk of WAVE_FORMAT_EXTENSIBLE files holds the actual format at the start of the sub format GUID
+        let mut body = fmt(FORMAT_EXTENSIBLE, 1, 48000, 32)[8..].to_vec();
+        body.extend(22u16.to_le_bytes());
+        body.extend(32u16.to_le_bytes());
+        body.extend(4u32.to_le_bytes());
+        body.extend(FORMAT_IEEE_FLOAT.to_le_bytes());
+        body.extend([0; 14]);
+        let audio = WavAudio::from_bytes(&riff(&[chunk(b"fmt ", &body), chunk(b"data", &0.5f32.to_le_bytes())])).unwrap();
+
+        assert_samples(&audio, &[0.5]);
+    }
+
+    #[test]
+    fn stereo_is_mixed_down() {
+        let data: Vec<u8> = [16384i16, -16384, 32767, 32767, 0, -32768].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        let audio = WavAudio::from_bytes(&riff(&[fmt(FORMAT_PCM, 2, 8000, 16), chunk(b"data", &data)])).unwrap();
+
+        assert_samples(&audio, &[0.0, 32767.0 / 32768.0, -0.5]);
+    }
+
+    #[test]
+    fn unknown_and_odd_sized_chunks_are_skipped() {
+        let data: Vec<u8> = [16384i16].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        let bytes = riff(&[chunk(b"LIST", &[1, 2, 3]), fmt(FORMAT_PCM, 1, 8000, 16), chunk(b"junk", &[0; 5]), chunk(b"data", &data)]);
+
+        assert_samples(&WavAudio::from_bytes(&bytes).unwrap(), &[0.5]);
+    }
+
+    #[test]
+    fn truncated_data_is_read_up_to_the_last_whole_frame() {
+        let mut bytes = riff(&[fmt(FORMAT_PCM, 1, 8000, 16), chunk(b"data", &[0, 0x40, 0, 0x40])]);
+        bytes.truncate(bytes.len() - 1);
+
+        assert_samples(&WavAudio::from_bytes(&bytes).unwrap(), &[0.5]);
+    }
+
+    #[test]
+    fn malformed_files_are_rejected() {
+        let data = chunk(b"data", &[0, 0]);
+
+        assert!(WavAudio::from_bytes(b"RIFF").is_err());
+        assert!(WavAudio::from_bytes(&[b"RIFX".to_vec(), riff(&[fmt(FORMAT_PCM, 1, 8000, 16), data.clone()])[4..].to_vec()].concat()).is_err());
+        assert!(WavAudio::from_bytes(&riff(&[chunk(b"data", &[0, 0])])).is_err());
+        assert!(WavAudio::from_bytes(&riff(&[fmt(FORMAT_PCM, 1, 8000, 16)])).is_err());
+        assert!(WavAudio::from_bytes(&riff(&[chunk(b"fmt ", &[1, 0, 1, 0]), data.clone()])).is_err());
+        assert!(WavAudio::from_bytes(&riff(&[fmt(FORMAT_PCM, 0, 8000, 16), data.clone()])).is_err());
+        assert!(WavAudio::from_bytes(&riff(&[fmt(FORMAT_PCM, 1, 0, 16), data.clone()])).is_err());
+        assert!(WavAudio::from_bytes(&riff(&[fmt(FORMAT_PCM, 1, 8000, 12), data.clone()])).is_err());
+        assert!(WavAudio::from_bytes(&riff(&[fmt(2, 1, 8000, 16), data])).is_err());
+    }
+}
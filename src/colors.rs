@@ -1,7 +1,7 @@
 pub mod colors {
 
-    #[derive(PartialEq)]
-    enum BlendMode {
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub enum BlendMode {
         NORMAL,
         MULTIPLY,
         SCREEN,
@@ -9,7 +9,17 @@ pub mod colors {
         LIGHTEN,
         OVERLAY,
         SOFT_LIGHT,
-        HARD_LIGHT
+        HARD_LIGHT,
+        COLOR_DODGE,
+        COLOR_BURN,
+        DIFFERENCE,
+        EXCLUSION,
+        ADD,
+        SUBTRACT,
+        HUE,
+        SATURATION,
+        COLOR,
+        LUMINOSITY
     }
 
     impl BlendMode {
@@ -81,11 +91,89 @@ pub mod colors {
                         d = backdrop[i].powf(0.5);
                     }
 
-                    backdrop[i] = (a * (backdrop[i] + (2.0 * source[i] - 1.0) * (d - backdrop[i])) - b * backdrop[i]);
+                    backdrop[i] = (a * (backdrop[i] + (2.0 * source[i] - 1.0) * (d - backdrop[i])) + b * backdrop[i]);
                 }
             }
         }
 
+        fn blend_color_dodge (source: &[f32; 4], backdrop: &mut [f32; 4], a: f32, b: f32) {
+            for i in 0..4 {
+                let blended = if backdrop[i] <= 0.0 {
+                    0.0
+                } else if source[i] >= 1.0 {
+                    1.0
+                } else {
+                    (backdrop[i] / (1.0 - source[i])).min(1.0)
+                };
+                backdrop[i] = a * blended + b * backdrop[i];
+            }
+        }
+
+        fn blend_color_burn (source: &[f32; 4], backdrop: &mut [f32; 4], a: f32, b: f32) {
+            for i in 0..4 {
+                let blended = if backdrop[i] >= 1.0 {
+                    1.0
+                } else if source[i] <= 0.0 {
+                    0.0
+                } else {
+                    1.0 - ((1.0 - backdrop[i]) / source[i]).min(1.0)
+                };
+                backdrop[i] = a * blended + b * backdrop[i];
+            }
+        }
+
+        fn blend_difference (source: &[f32; 4], backdrop: &mut [f32; 4], a: f32, b: f32) {
+            for i in 0..4 {
+                backdrop[i] = a * (backdrop[i] - source[i]).abs() + b * backdrop[i];
+            }
+        }
+
+        fn blend_exclusion (source: &[f32; 4], backdrop: &mut [f32; 4], a: f32, b: f32) {
+            for i in 0..4 {
+                backdrop[i] = a * (backdrop[i] + source[i] - 2.0 * backdrop[i] * source[i]) + b * backdrop[i];
+            }
+        }
+
+        /** Also known as linear dodge */
+        fn blend_add (source: &[f32; 4], backdrop: &mut [f32; 4], a: f32, b: f32) {
+            for i in 0..4 {
+                backdrop[i] = a * (backdrop[i] + source[i]).min(1.0) + b * backdrop[i];
+            }
+        }
+
+        fn blend_subtract (source: &[f32; 4], backdrop: &mut [f32; 4], a: f32, b: f32) {
+            for i in 0..4 {
+                backdrop[i] = a * (backdrop[i] - source[i]).max(0.0) + b * backdrop[i];
+            }
+        }
+
+        /**
+         * Applies a non-separable blending function, which works on the RGB channels as a
+         * whole instead of channel by channel. The alpha channel is left untouched.
+         */
+        fn blend_non_separable (source: &[f32; 4], backdrop: &mut [f32; 4], a: f32, b: f32, func: fn(&[f32; 3], &[f32; 3]) -> [f32; 3]) {
+            let blended = func(&[source[0], source[1], source[2]], &[backdrop[0], backdrop[1], backdrop[2]]);
+            for i in 0..3 {
+                backdrop[i] = a * blended[i] + b * backdrop[i];
+            }
+        }
+
+        fn hue (source: &[f32; 3], backdrop: &[f32; 3]) -> [f32; 3] {
+            set_lum(&set_sat(source, sat(backdrop)), lum(backdrop))
+        }
+
+        fn saturation (source: &[f32; 3], backdrop: &[f32; 3]) -> [f32; 3] {
+            set_lum(&set_sat(backdrop, sat(source)), lum(backdrop))
+        }
+
+        fn color (source: &[f32; 3], backdrop: &[f32; 3]) -> [f32; 3] {
+            set_lum(source, lum(backdrop))
+        }
+
+        fn luminosity (source: &[f32; 3], backdrop: &[f32; 3]) -> [f32; 3] {
+            set_lum(backdrop, lum(source))
+        }
+
         /**
          * Blend two colors according to standard alpha compositing rules, using the given 
          * blending mode and updating the second color in-place
@@ -124,10 +212,448 @@ pub mod colors {
                 BlendMode::OVERLAY => BlendMode::blend_overlay(source, backdrop, a, b),
                 BlendMode::SOFT_LIGHT => BlendMode::blend_soft_light(source, backdrop, a, b),
                 BlendMode::HARD_LIGHT => BlendMode::blend_hard_light(source, backdrop, a, b),
+                BlendMode::COLOR_DODGE => BlendMode::blend_color_dodge(source, backdrop, a, b),
+                BlendMode::COLOR_BURN => BlendMode::blend_color_burn(source, backdrop, a, b),
+                BlendMode::DIFFERENCE => BlendMode::blend_difference(source, backdrop, a, b),
+                BlendMode::EXCLUSION => BlendMode::blend_exclusion(source, backdrop, a, b),
+                BlendMode::ADD => BlendMode::blend_add(source, backdrop, a, b),
+                BlendMode::SUBTRACT => BlendMode::blend_subtract(source, backdrop, a, b),
+                BlendMode::HUE => BlendMode::blend_non_separable(source, backdrop, a, b, BlendMode::hue),
+                BlendMode::SATURATION => BlendMode::blend_non_separable(source, backdrop, a, b, BlendMode::saturation),
+                BlendMode::COLOR => BlendMode::blend_non_separable(source, backdrop, a, b, BlendMode::color),
+                BlendMode::LUMINOSITY => BlendMode::blend_non_separable(source, backdrop, a, b, BlendMode::luminosity),
             }
 
             backdrop[3] = alpha_overlay;
         }
 
     }
-}
\ No newline at end of file
+
+    /*
+     * Helper functions of the non-separable blend modes, as defined by the W3C
+     * compositing and blending specification. See https://www.w3.org/TR/compositing-1/
+     */
+
+    fn lum(color: &[f32; 3]) -> f32 {
+        0.3 * color[0] + 0.59 * color[1] + 0.11 * color[2]
+    }
+
+    fn clip_color(color: &[f32; 3]) -> [f32; 3] {
+        let l = lum(color);
+        let n = color[0].min(color[1]).min(color[2]);
+        let x = color[0].max(color[1]).max(color[2]);
+        let mut clipped = *color;
+
+        if n < 0.0 {
+            for channel in clipped.iter_mut() {
+                *channel = l + (*channel - l) * l / (l - n);
+            }
+        }
+        if x > 1.0 {
+            for channel in clipped.iter_mut() {
+                *channel = l + (*channel - l) * (1.0 - l) / (x - l);
+            }
+        }
+
+        clipped
+    }
+
+    fn set_lum(color: &[f32; 3], l: f32) -> [f32; 3] {
+        let d = l - lum(color);
+        clip_color(&[color[0] + d, color[1] + d, color[2] + d])
+    }
+
+    fn sat(color: &[f32; 3]) -> f32 {
+        color[0].max(color[1]).max(color[2]) - color[0].min(color[1]).min(color[2])
+    }
+
+    fn set_sat(color: &[f32; 3], s: f32) -> [f32; 3] {
+        //Indices of the channels sorted from the smallest to the largest value
+        let mut order = [0, 1, 2];
+        order.sort_by(|i, j| color[*i].total_cmp(&color[*j]));
+        let (min, mid, max) = (order[0], order[1], order[2]);
+        let mut result = [0.0; 3];
+
+        if color[max] > color[min] {
+            result[mid] = (color[mid] - color[min]) * s / (color[max] - color[min]);
+            result[max] = s;
+        }
+
+        result
+    }
+
+    /**
+     * Color spaces in which colors can be interpolated and compared.
+     *
+     * RGB interpolation produces muddy midpoints between saturated colors (e.g. red to green
+     * passes through brown). HSV and HSL travel around the hue wheel instead, and OKLab is a
+     * perceptually uniform space where equal distances look like equal differences.
+     */
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub enum ColorSpace {
+        RGB,
+        HSV,
+        HSL,
+        OKLAB
+    }
+
+    impl ColorSpace {
+        /** Converts a color from RGB (channels in [0-1]) to this color space */
+        pub fn from_rgb(&self, rgb: &[f32; 3]) -> [f32; 3] {
+            match self {
+                ColorSpace::RGB => *rgb,
+                ColorSpace::HSV => rgb_to_hsv(rgb),
+                ColorSpace::HSL => rgb_to_hsl(rgb),
+                ColorSpace::OKLAB => rgb_to_oklab(rgb),
+            }
+        }
+
+        /** Converts a color from this color space to RGB (channels in [0-1]) */
+        pub fn to_rgb(&self, color: &[f32; 3]) -> [f32; 3] {
+            match self {
+                ColorSpace::RGB => *color,
+                ColorSpace::HSV => hsv_to_rgb(color),
+                ColorSpace::HSL => hsl_to_rgb(color),
+                ColorSpace::OKLAB => oklab_to_rgb(color),
+            }
+        }
+
+        /**
+         * Linearly interpolates between two RGB colors in this color space. Hues take the
+         * shortest way around the hue wheel.
+         *
+         * Returns:
+         *     the interpolated color in RGB, with channels clamped to [0-1]
+         */
+        pub fn interpolate(&self, from: &[f32; 3], to: &[f32; 3], ratio: f32) -> [f32; 3] {
+            let mut a = self.from_rgb(from);
+            let mut b = self.from_rgb(to);
+
+            if *self == ColorSpace::HSV || *self == ColorSpace::HSL {
+                //The hue of a gray is meaningless, so take the one of the other color
+                if a[1] <= 0.0 { a[0] = b[0] }
+                if b[1] <= 0.0 { b[0] = a[0] }
+                if b[0] - a[0] > 180.0 { a[0] += 360.0 }
+                if a[0] - b[0] > 180.0 { b[0] += 360.0 }
+            }
+
+            let mut mixed = [0.0; 3];
+            for i in 0..3 {
+                mixed[i] = a[i] + ratio * (b[i] - a[i]);
+            }
+            if *self == ColorSpace::HSV || *self == ColorSpace::HSL {
+                mixed[0] = mixed[0].rem_euclid(360.0);
+            }
+
+            let rgb = self.to_rgb(&mixed);
+            [rgb[0].clamp(0.0, 1.0), rgb[1].clamp(0.0, 1.0), rgb[2].clamp(0.0, 1.0)]
+        }
+    }
+
+    /**
+     * Perceptual distance between two RGB colors (channels in [0-1]), measured as the
+     * euclidean distance in OKLab. A difference of about 0.02 is barely noticeable.
+     */
+    pub fn perceptual_distance(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+        let (p, q) = (rgb_to_oklab(a), rgb_to_oklab(b));
+        ((p[0] - q[0]).powi(2) + (p[1] - q[1]).powi(2) + (p[2] - q[2]).powi(2)).sqrt()
+    }
+
+    /** Converts RGB to HSV as [hue in degrees [0-360), saturation [0-1], value [0-1]] */
+    pub fn rgb_to_hsv(rgb: &[f32; 3]) -> [f32; 3] {
+        let max = rgb[0].max(rgb[1]).max(rgb[2]);
+        let min = rgb[0].min(rgb[1]).min(rgb[2]);
+        let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+
+        [hue(rgb, max, min), saturation, max]
+    }
+
+    pub fn hsv_to_rgb(hsv: &[f32; 3]) -> [f32; 3] {
+        let chroma = hsv[2] * hsv[1];
+        from_hue_chroma(hsv[0], chroma, hsv[2] - chroma)
+    }
+
+    /** Converts RGB to HSL as [hue in degrees [0-360), saturation [0-1], lightness [0-1]] */
+    pub fn rgb_to_hsl(rgb: &[f32; 3]) -> [f32; 3] {
+        let max = rgb[0].max(rgb[1]).max(rgb[2]);
+        let min = rgb[0].min(rgb[1]).min(rgb[2]);
+        let lightness = (max + min) / 2.0;
+        let saturation = if max > min { (max - min) / (1.0 - (2.0 * lightness - 1.0).abs()) } else { 0.0 };
+
+        [hue(rgb, max, min), saturation, lightness]
+    }
+
+    pub fn hsl_to_rgb(hsl: &[f32; 3]) -> [f32; 3] {
+        let chroma = (1.0 - (2.0 * hsl[2] - 1.0).abs()) * hsl[1];
+        from_hue_chroma(hsl[0], chroma, hsl[2] - chroma / 2.0)
+    }
+
+    /**
+     * Converts gamma-encoded sRGB to OKLab as [lightness, a, b].
+     * See https://bottosson.github.io/posts/oklab/
+     */
+    pub fn rgb_to_oklab(rgb: &[f32; 3]) -> [f32; 3] {
+        let r = srgb_to_linear(rgb[0]);
+        let g = srgb_to_linear(rgb[1]);
+        let b = srgb_to_linear(rgb[2]);
+
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+
+        [
+            0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        ]
+    }
+
+    pub fn oklab_to_rgb(lab: &[f32; 3]) -> [f32; 3] {
+        let l = (lab[0] + 0.396_337_78 * lab[1] + 0.215_803_76 * lab[2]).powi(3);
+        let m = (lab[0] - 0.105_561_346 * lab[1] - 0.063_854_17 * lab[2]).powi(3);
+        let s = (lab[0] - 0.089_484_18 * lab[1] - 1.291_485_5 * lab[2]).powi(3);
+
+        [
+            linear_to_srgb(4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s),
+            linear_to_srgb(-1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s),
+            linear_to_srgb(-0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s),
+        ]
+    }
+
+    /* Hue in degrees of an RGB color given its largest and smallest channels */
+    fn hue(rgb: &[f32; 3], max: f32, min: f32) -> f32 {
+        let chroma = max - min;
+        if chroma <= 0.0 { return 0.0 }
+
+        let sector = if max == rgb[0] {
+            ((rgb[1] - rgb[2]) / chroma).rem_euclid(6.0)
+        } else if max == rgb[1] {
+            (rgb[2] - rgb[0]) / chroma + 2.0
+        } else {
+            (rgb[0] - rgb[1]) / chroma + 4.0
+        };
+
+        (sector * 60.0).rem_euclid(360.0)
+    }
+
+    /* RGB color from its hue in degrees, its chroma and the value added to every channel */
+    fn from_hue_chroma(hue: f32, chroma: f32, m: f32) -> [f32; 3] {
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector.rem_euclid(2.0) - 1.0).abs());
+
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+
+        [r + m, g + m, b + m]
+    }
+
+    fn srgb_to_linear(value: f32) -> f32 {
+        if value <= 0.04045 { value / 12.92 } else { ((value + 0.055) / 1.055).powf(2.4) }
+    }
+
+    fn linear_to_srgb(value: f32) -> f32 {
+        if value <= 0.003_130_8 { value * 12.92 } else { 1.055 * value.powf(1.0 / 2.4) - 0.055 }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /* Blends two opaque colors, so the result is the blending function B(Cb, Cs) of the W3C spec */
+        fn blend(source: [f32; 3], backdrop: [f32; 3], mode: BlendMode) -> [f32; 3] {
+            let mut result = [backdrop[0], backdrop[1], backdrop[2], 1.0];
+            BlendMode::blend_in_place(&[source[0], source[1], source[2], 1.0], &mut result, mode);
+            assert_eq!(result[3], 1.0);
+            [result[0], result[1], result[2]]
+        }
+
+        fn assert_color(actual: [f32; 3], expected: [f32; 3]) {
+            for i in 0..3 {
+                assert!((actual[i] - expected[i]).abs() < 1e-4, "{:?} != {:?}", actual, expected);
+            }
+        }
+
+        #[test]
+        fn color_dodge() {
+            //Cb / (1 - Cs) clamped to 1; Cb = 0 gives 0 even when Cs = 1
+            assert_color(blend([0.5, 0.5, 1.0], [0.25, 0.6, 0.0], BlendMode::COLOR_DODGE), [0.5, 1.0, 0.0]);
+            assert_color(blend([1.0, 0.0, 0.2], [0.5, 0.3, 0.4], BlendMode::COLOR_DODGE), [1.0, 0.3, 0.5]);
+        }
+
+        #[test]
+        fn color_burn() {
+            //1 - (1 - Cb) / Cs clamped to 0; Cb = 1 gives 1 even when Cs = 0
+            assert_color(blend([0.0, 0.5, 0.5], [1.0, 0.75, 0.25], BlendMode::COLOR_BURN), [1.0, 0.5, 0.0]);
+            assert_color(blend([0.0, 0.8, 1.0], [0.5, 0.6, 0.3], BlendMode::COLOR_BURN), [0.0, 0.5, 0.3]);
+        }
+
+        #[test]
+        fn difference() {
+            assert_color(blend([0.2, 0.75, 0.5], [0.5, 0.25, 0.5], BlendMode::DIFFERENCE), [0.3, 0.5, 0.0]);
+        }
+
+        #[test]
+        fn exclusion() {
+            //Cb + Cs - 2 * Cb * Cs
+            assert_color(blend([0.5, 1.0, 0.75], [0.5, 0.25, 0.0], BlendMode::EXCLUSION), [0.5, 0.75, 0.75]);
+        }
+
+        #[test]
+        fn add_and_subtract() {
+            assert_color(blend([0.25, 0.5, 0.75], [0.25, 0.25, 0.5], BlendMode::ADD), [0.5, 0.75, 1.0]);
+            assert_color(blend([0.25, 0.5, 0.75], [0.75, 0.25, 1.0], BlendMode::SUBTRACT), [0.5, 0.0, 0.25]);
+        }
+
+        #[test]
+        fn soft_light_darkens_below_half() {
+            //Cb - (1 - 2 * Cs) * Cb * (1 - Cb): 0.5 - 0.5 * 0.25 and 0.5 - 1 * 0.25; Cs = 0.5 keeps Cb
+            assert_color(blend([0.25, 0.0, 0.5], [0.5, 0.5, 0.3], BlendMode::SOFT_LIGHT), [0.375, 0.25, 0.3]);
+        }
+
+        #[test]
+        fn soft_light_lightens_above_half() {
+            //Cb + (2 * Cs - 1) * (D(Cb) - Cb), with D(0.25) = ((16 * 0.25 - 12) * 0.25 + 4) * 0.25 = 0.5
+            //and D(0.64) = sqrt(0.64) = 0.8
+            assert_color(blend([0.75, 0.75, 1.0], [0.25, 0.64, 0.25], BlendMode::SOFT_LIGHT), [0.375, 0.72, 0.5]);
+        }
+
+        #[test]
+        fn hue() {
+            //SetLum(SetSat(Cs, 0.4), 0.52) = [0, 0.4, 0] + 0.284
+            assert_color(blend([0.0, 1.0, 0.0], [0.8, 0.4, 0.4], BlendMode::HUE), [0.284, 0.684, 0.284]);
+            //A gray backdrop has no saturation, so the hue of the source is lost
+            assert_color(blend([1.0, 0.0, 0.0], [0.5, 0.5, 0.5], BlendMode::HUE), [0.5, 0.5, 0.5]);
+        }
+
+        #[test]
+        fn saturation() {
+            //SetSat(Cb, 1) = [1, 0.5, 0], moved to luminosity 0.438 and clipped below 0 around it
+            assert_color(blend([1.0, 0.0, 0.5], [0.6, 0.4, 0.2], BlendMode::SATURATION), [0.736_134_5, 0.368_067_2, 0.0]);
+        }
+
+        #[test]
+        fn color() {
+            //[1.6, 0.6, 0.6] has luminosity 0.9 and is clipped above 1: 0.9 + (C - 0.9) * 0.1 / 0.7
+            assert_color(blend([1.0, 0.0, 0.0], [0.9, 0.9, 0.9], BlendMode::COLOR), [1.0, 0.857_142_9, 0.857_142_9]);
+            //[-0.06, -0.06, 0.94] has luminosity 0.05 and is clipped below 0: 0.05 + (C - 0.05) * 0.05 / 0.11
+            assert_color(blend([0.0, 0.0, 1.0], [0.05, 0.05, 0.05], BlendMode::COLOR), [0.0, 0.0, 0.454_545_5]);
+        }
+
+        #[test]
+        fn luminosity() {
+            assert_color(blend([0.5, 0.5, 0.5], [0.2, 0.4, 0.6], BlendMode::LUMINOSITY), [0.338, 0.538, 0.738]);
+            //Full luminosity clips every channel to white
+            assert_color(blend([1.0, 1.0, 1.0], [1.0, 0.0, 0.0], BlendMode::LUMINOSITY), [1.0, 1.0, 1.0]);
+        }
+
+        #[test]
+        fn clip_color_edge_cases() {
+            assert_color(clip_color(&[0.2, 0.4, 0.6]), [0.2, 0.4, 0.6]);
+            //Both clips apply; the second one uses the largest channel of the unclipped color
+            assert_color(clip_color(&[-0.5, 0.5, 1.5]), [0.130_252_1, 0.352_163_1, 0.574_074_1]);
+        }
+
+        #[test]
+        fn non_separable_modes_keep_alpha_compositing() {
+            //Half transparent source over an opaque backdrop: 0.5 * B(Cb, Cs) + 0.5 * Cb
+            let mut result = [0.8, 0.4, 0.4, 1.0];
+            BlendMode::blend_in_place(&[0.0, 1.0, 0.0, 0.5], &mut result, BlendMode::HUE);
+            assert_color([result[0], result[1], result[2]], [0.542, 0.542, 0.342]);
+            assert_eq!(result[3], 1.0);
+        }
+
+        /* Primaries, secondaries and grays */
+        const SAMPLES: [[f32; 3]; 10] = [
+            [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0],
+            [1.0, 1.0, 0.0], [0.0, 1.0, 1.0], [1.0, 0.0, 1.0],
+            [0.0, 0.0, 0.0], [1.0, 1.0, 1.0], [0.5, 0.5, 0.5], [0.2, 0.4, 0.6],
+        ];
+
+        #[test]
+        fn color_space_round_trips() {
+            for space in [ColorSpace::RGB, ColorSpace::HSV, ColorSpace::HSL, ColorSpace::OKLAB] {
+                for rgb in SAMPLES {
+                    assert_color(space.to_rgb(&space.from_rgb(&rgb)), rgb);
+                }
+            }
+        }
+
+        #[test]
+        fn color_space_reference_values() {
+            assert_color(rgb_to_hsv(&[1.0, 0.0, 0.0]), [0.0, 1.0, 1.0]);
+            assert_color(rgb_to_hsv(&[0.0, 0.5, 1.0]), [210.0, 1.0, 1.0]);
+            assert_color(rgb_to_hsv(&[1.0, 0.0, 1.0]), [300.0, 1.0, 1.0]);
+            assert_color(rgb_to_hsl(&[0.0, 0.0, 1.0]), [240.0, 1.0, 0.5]);
+            assert_color(rgb_to_hsl(&[0.75, 0.25, 0.25]), [0.0, 0.5, 0.5]);
+            //Reference values from https://bottosson.github.io/posts/oklab/
+            assert_color(rgb_to_oklab(&[1.0, 1.0, 1.0]), [1.0, 0.0, 0.0]);
+            assert_color(rgb_to_oklab(&[0.0, 0.0, 0.0]), [0.0, 0.0, 0.0]);
+            assert_color(rgb_to_oklab(&[1.0, 0.0, 0.0]), [0.627_955, 0.224_863, 0.125_846]);
+        }
+
+        #[test]
+        fn grays_have_no_hue_or_saturation() {
+            for gray in [0.0, 0.5, 1.0] {
+                assert_color(rgb_to_hsv(&[gray, gray, gray]), [0.0, 0.0, gray]);
+                assert_color(rgb_to_hsl(&[gray, gray, gray]), [0.0, 0.0, gray]);
+
+                let lab = rgb_to_oklab(&[gray, gray, gray]);
+                assert!(lab[1].abs() < 1e-4 && lab[2].abs() < 1e-4, "{:?}", lab);
+            }
+        }
+
+        #[test]
+        fn interpolation_ends() {
+            for space in [ColorSpace::RGB, ColorSpace::HSV, ColorSpace::HSL, ColorSpace::OKLAB] {
+                assert_color(space.interpolate(&[1.0, 0.0, 0.0], &[0.0, 0.5, 1.0], 0.0), [1.0, 0.0, 0.0]);
+                assert_color(space.interpolate(&[1.0, 0.0, 0.0], &[0.0, 0.5, 1.0], 1.0), [0.0, 0.5, 1.0]);
+            }
+        }
+
+        #[test]
+        fn interpolation_in_each_space() {
+            let (red, green) = ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+
+            assert_color(ColorSpace::RGB.interpolate(&red, &green, 0.5), [0.5, 0.5, 0.0]);
+            //Around the hue wheel through yellow instead of brown
+            assert_color(ColorSpace::HSV.interpolate(&red, &green, 0.5), [1.0, 1.0, 0.0]);
+            assert_color(ColorSpace::HSL.interpolate(&red, &green, 0.5), [1.0, 1.0, 0.0]);
+
+            //Perceptual middle gray, darker than the RGB middle
+            let gray = ColorSpace::OKLAB.interpolate(&[0.0, 0.0, 0.0], &[1.0, 1.0, 1.0], 0.5);
+            assert_color(gray, [gray[0], gray[0], gray[0]]);
+            assert!((rgb_to_oklab(&gray)[0] - 0.5).abs() < 1e-4);
+            assert!(gray[0] < 0.45);
+        }
+
+        #[test]
+        fn interpolation_wraps_hue() {
+            for space in [ColorSpace::HSV, ColorSpace::HSL] {
+                let from = space.to_rgb(&[350.0, 1.0, if space == ColorSpace::HSV { 1.0 } else { 0.5 }]);
+                let to = space.to_rgb(&[10.0, 1.0, if space == ColorSpace::HSV { 1.0 } else { 0.5 }]);
+
+                //Through red at 0 degrees instead of around the wheel through cyan
+                assert_color(space.interpolate(&from, &to, 0.5), [1.0, 0.0, 0.0]);
+                assert_color(space.interpolate(&to, &from, 0.5), [1.0, 0.0, 0.0]);
+                assert!((space.from_rgb(&space.interpolate(&from, &to, 0.25))[0] - 355.0).abs() < 1e-3);
+                assert!((space.from_rgb(&space.interpolate(&from, &to, 0.75))[0] - 5.0).abs() < 1e-3);
+            }
+        }
+
+        #[test]
+        fn interpolation_from_grays_keeps_the_hue_of_the_color() {
+            //Gray to red only changes saturation and value, without passing through other hues
+            assert_color(ColorSpace::HSV.interpolate(&[0.5, 0.5, 0.5], &[1.0, 0.0, 0.0], 0.5), [0.75, 0.375, 0.375]);
+            assert_color(ColorSpace::HSV.interpolate(&[1.0, 0.0, 0.0], &[0.5, 0.5, 0.5], 0.5), [0.75, 0.375, 0.375]);
+
+            assert_color(ColorSpace::HSL.interpolate(&[1.0, 1.0, 1.0], &[0.0, 0.0, 1.0], 0.5), [0.625, 0.625, 0.875]);
+            assert_color(ColorSpace::HSV.interpolate(&[0.0, 0.0, 0.0], &[1.0, 1.0, 1.0], 0.5), [0.5, 0.5, 0.5]);
+        }
+    }
+}
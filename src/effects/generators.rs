@@ -0,0 +1,397 @@
+use std::f32::consts::PI;
+
+use crate::model::color::{Color3D, Color4D};
+use crate::model::light_program::LightProgram;
+use crate::model::tempo_map::TempoMap;
+use crate::utils::random::SeededRandom;
+
+//Number of keypoints per period used to approximate the sine wave of breathe()
+const BREATHE_STEPS: usize = 8;
+
+//Primary and secondary colors in hue order, used as keypoints of rainbow()
+const HUE_WHEEL: [[u8; 3]; 6] = [
+    [255, 0, 0],
+    [255, 255, 0],
+    [0, 255, 0],
+    [0, 255, 255],
+    [0, 0, 255],
+    [255, 0, 255],
+];
+
+/**
+ * Keeps a single color during the whole effect.
+ *
+ * Parameters:
+ *     start: start time of the effect, in seconds
+ *     end: end time of the effect, in seconds
+ *     color: the color to show
+ */
+pub fn solid(start: f32, end: f32, color: Color3D) -> LightProgram {
+    LightProgram::new(vec![color.at_time(start, false), color.at_time(end, false)])
+}
+
+/**
+ * Switches abruptly between two colors.
+ *
+ * Parameters:
+ *     start: start time of the effect, in seconds
+ *     end: end time of the effect, in seconds
+ *     on_color: the color shown at the start of each period
+ *     off_color: the color shown for the rest of each period
+ *     period: duration of one on/off cycle, in seconds
+ *     duty_cycle: fraction of the period in which on_color is shown, in the range [0-1]
+ */
+pub fn strobe(start: f32, end: f32, on_color: Color3D, off_color: Color3D, period: f32, duty_cycle: f32) -> LightProgram {
+    let mut colors: Vec<Color4D> = Vec::new();
+
+    for cycle_start in cycle_starts(start, end, period) {
+        colors.push(on_color.at_time(cycle_start, false));
+        let off_time = cycle_start + duty_cycle.clamp(0.0, 1.0) * period;
+        if off_time < end {
+            colors.push(off_color.at_time(off_time, false));
+        }
+    }
+
+    finish(colors, end)
+}
+
+/**
+ * Fades linearly back and forth between two colors (triangle wave).
+ *
+ * Parameters:
+ *     start: start time of the effect, in seconds
+ *     end: end time of the effect, in seconds
+ *     low_color: the color at the start and end of each period
+ *     high_color: the color at the middle of each period
+ *     period: duration of one low-high-low cycle, in seconds
+ */
+pub fn pulse(start: f32, end: f32, low_color: Color3D, high_color: Color3D, period: f32) -> LightProgram {
+    let mut colors: Vec<Color4D> = Vec::new();
+
+    let (low, high) = (low_color.as_array(), high_color.as_array());
+
+    for cycle_start in cycle_starts(start, end, period) {
+        colors.push(low_color.at_time(cycle_start, cycle_start > start));
+        let peak = cycle_start + period / 2.0;
+        if peak < end {
+            colors.push(high_color.at_time(peak, true));
+        }
+    }
+
+    let phase = end_phase(start, end, period);
+    close_wave(colors, end, Color3D::from_array(&mix(&low, &high, 1.0 - (1.0 - 2.0 * phase).abs())))
+}
+
+/**
+ * Fades smoothly back and forth between two colors following a sine wave, like a
+ * breathing light.
+ *
+ * Parameters: the same as pulse()
+ */
+pub fn breathe(start: f32, end: f32, low_color: Color3D, high_color: Color3D, period: f32) -> LightProgram {
+    let mut colors: Vec<Color4D> = Vec::new();
+    let (low, high) = (low_color.as_array(), high_color.as_array());
+
+    for cycle_start in cycle_starts(start, end, period) {
+        for step in 0..BREATHE_STEPS {
+            let phase = step as f32 / BREATHE_STEPS as f32;
+            let t = cycle_start + phase * period;
+            if t >= end { break }
+
+            colors.push(Color3D::from_array(&mix(&low, &high, breathe_ratio(phase))).at_time(t, t > start));
+        }
+    }
+
+    let phase = end_phase(start, end, period);
+    close_wave(colors, end, Color3D::from_array(&mix(&low, &high, breathe_ratio(phase))))
+}
+
+/**
+ * Cycles through the hue wheel at full saturation.
+ *
+ * Parameters:
+ *     start: start time of the effect, in seconds
+ *     end: end time of the effect, in seconds
+ *     period: duration of a full turn of the hue wheel, in seconds
+ *     offset: position on the hue wheel at the start of the effect, in the range [0-1);
+ *             different offsets per drone make the rainbow travel across the formation
+ */
+pub fn rainbow(start: f32, end: f32, period: f32, offset: f32) -> LightProgram {
+    let mut colors: Vec<Color4D> = Vec::new();
+    let steps = HUE_WHEEL.len();
+    let step_duration = period / steps as f32;
+    if step_duration <= 0.0 { return LightProgram::new(colors) }
+
+    let offset = offset.rem_euclid(1.0) * steps as f32;
+    let hue = |position: f32| {
+        let index = position.floor() as usize;
+        let from = HUE_WHEEL[index % steps];
+        let to = HUE_WHEEL[(index + 1) % steps];
+        let ratio = position - position.floor();
+        Color3D::from_array(&mix(&rgb_array(from), &rgb_array(to), ratio))
+    };
+
+    colors.push(hue(offset).at_time(start, false));
+    let mut index = offset.floor() as usize + 1;
+    loop {
+        let t = start + (index as f32 - offset) * step_duration;
+        if t >= end { break }
+        colors.push(rgb_color(HUE_WHEEL[index % steps]).at_time(t, true));
+        index += 1;
+    }
+    colors.push(hue(offset + (end - start) / step_duration).at_time(end, true));
+
+    LightProgram::new(colors)
+}
+
+/**
+ * Flashes a sparkle color at random moments over a base color.
+ *
+ * Parameters:
+ *     start: start time of the effect, in seconds
+ *     end: end time of the effect, in seconds
+ *     base_color: the color shown between sparkles
+ *     sparkle_color: the color of each sparkle
+ *     flash_duration: duration of a single sparkle, in seconds
+ *     density: probability in the range [0-1] that each flash_duration slot sparkles
+ *     seed: seed of the random generator; use a different one per drone
+ */
+pub fn sparkle(start: f32, end: f32, base_color: Color3D, sparkle_color: Color3D, flash_duration: f32, density: f32, seed: u64) -> LightProgram {
+    let mut random = SeededRandom::new(seed);
+    let mut colors: Vec<Color4D> = vec![base_color.at_time(start, false)];
+
+    for slot_start in cycle_starts(start, end, flash_duration) {
+        if random.next_f32() < density {
+            colors.push(sparkle_color.at_time(slot_start, false));
+            colors.push(base_color.at_time((slot_start + flash_duration).min(end), false));
+        }
+    }
+
+    finish(colors, end)
+}
+
+/**
+ * Steps through a list of colors, one color per step. Giving consecutive drones
+ * consecutive phases makes the colors chase each other across the formation.
+ *
+ * Parameters:
+ *     start: start time of the effect, in seconds
+ *     end: end time of the effect, in seconds
+ *     colors: the colors to step through, in order
+ *     step: duration of each color, in seconds
+ *     phase: number of steps the sequence is advanced at the start of the effect
+ */
+pub fn color_chase(start: f32, end: f32, colors: &[Color3D], step: f32, phase: usize) -> LightProgram {
+    if colors.is_empty() { return LightProgram::new(Vec::new()) }
+
+    let keypoints: Vec<Color4D> = cycle_starts(start, end, step)
+        .into_iter()
+        .enumerate()
+        .map(|(index, t)| colors[(index + phase) % colors.len()].at_time(t, false))
+        .collect();
+
+    finish(keypoints, end)
+}
+
+/**
+ * Fades linearly from a color to black.
+ *
+ * Parameters:
+ *     start: time when the fade starts, in seconds
+ *     end: time when the LEDs are completely off, in seconds
+ *     color: the color at the start of the fade
+ */
+pub fn fade_to_black(start: f32, end: f32, color: Color3D) -> LightProgram {
+    LightProgram::new(vec![color.at_time(start, false), Color3D::new(0, 0, 0).at_time(end, true)])
+}
+
+/**
+ * Syncs an effect to the tempo of the song. The effect is generated with every time and
+ * duration given in beats, and its keypoints are then moved to the time of those beats, so
+ * it follows tempo changes. For instance, a strobe with a period of 1 flashes on every beat
+ * and one with a period of 0.5 on every eighth note in 4/4.
+ *
+ * Parameters:
+ *     map: the tempo map of the song
+ *     start: start of the effect, in beats from 1:1:0; see TempoMap.beats_at()
+ *     end: end of the effect, in beats from 1:1:0
+ *     generate: creates the effect from its start and end in beats, e.g. a call to strobe()
+ */
+pub fn in_tempo(map: &TempoMap, start: f32, end: f32, generate: impl Fn(f32, f32) -> LightProgram) -> LightProgram {
+    generate(start, end).retime(|beats| map.beats_to_seconds(beats))
+}
+
+/* Start times of the consecutive cycles of the given period between start and end */
+fn cycle_starts(start: f32, end: f32, period: f32) -> Vec<f32> {
+    if period <= 0.0 || end <= start { return Vec::new() }
+
+    let num_cycles = ((end - start) / period).ceil() as usize;
+    (0..num_cycles).map(|index| start + index as f32 * period).collect()
+}
+
+/* Closes a list of keypoints with the color that the program shows at the end time */
+fn finish(colors: Vec<Color4D>, end: f32) -> LightProgram {
+    let mut program = LightProgram::new(colors);
+    if let Some(color) = program.color_at(end) {
+        let is_fade = program.get_colors().last().is_some_and(|last| last.t < end);
+        if is_fade {
+            program.append(color.at_time(end, true));
+        }
+    }
+
+    program
+}
+
+/* Fraction of the last period of a wave that has elapsed at the end of the effect */
+fn end_phase(start: f32, end: f32, period: f32) -> f32 {
+    if period <= 0.0 { 0.0 } else { ((end - start) / period).fract() }
+}
+
+/* Closes the keypoints of a wave with its color at the end time */
+fn close_wave(mut colors: Vec<Color4D>, end: f32, color: Color3D) -> LightProgram {
+    if colors.last().is_some_and(|last| last.t < end) {
+        colors.push(color.at_time(end, true));
+    }
+
+    LightProgram::new(colors)
+}
+
+/* Position between the low and high colors of breathe() at a phase of its period */
+fn breathe_ratio(phase: f32) -> f32 {
+    (1.0 - (2.0 * PI * phase).cos()) / 2.0
+}
+
+fn mix(from: &[f32; 4], to: &[f32; 4], ratio: f32) -> [f32; 4] {
+    [
+        from[0] + ratio * (to[0] - from[0]),
+        from[1] + ratio * (to[1] - from[1]),
+        from[2] + ratio * (to[2] - from[2]),
+        1.0
+    ]
+}
+
+fn rgb_color(rgb: [u8; 3]) -> Color3D {
+    Color3D::new(rgb[0], rgb[1], rgb[2])
+}
+
+fn rgb_array(rgb: [u8; 3]) -> [f32; 4] {
+    rgb_color(rgb).as_array()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::tempo_map::MusicalPosition;
+
+    fn red() -> Color3D { Color3D::new(255, 0, 0) }
+    fn green() -> Color3D { Color3D::new(0, 255, 0) }
+    fn blue() -> Color3D { Color3D::new(0, 0, 255) }
+    fn white() -> Color3D { Color3D::new(255, 255, 255) }
+    fn black() -> Color3D { Color3D::new(0, 0, 0) }
+
+    /* Keypoints of a program as (time, color, is_fade) */
+    fn keypoints(program: &LightProgram) -> Vec<(f32, Color3D, bool)> {
+        program.get_colors().iter().map(|color| (color.t, color.as_3d(), color.is_fade())).collect()
+    }
+
+    #[test]
+    fn solid_and_fade_to_black() {
+        assert_eq!(keypoints(&solid(1.0, 3.0, red())), vec![(1.0, red(), false), (3.0, red(), false)]);
+        assert_eq!(keypoints(&fade_to_black(1.0, 3.0, red())), vec![(1.0, red(), false), (3.0, black(), true)]);
+        assert_eq!(fade_to_black(1.0, 3.0, red()).color_at(2.0), Some(Color3D::new(128, 0, 0)));
+    }
+
+    #[test]
+    fn strobe_period_and_duty_cycle() {
+        let program = strobe(0.0, 2.2, white(), black(), 1.0, 0.25);
+
+        assert_eq!(keypoints(&program), vec![
+            (0.0, white(), false), (0.25, black(), false),
+            (1.0, white(), false), (1.25, black(), false),
+            (2.0, white(), false), (2.2, white(), true),
+        ]);
+        assert!(strobe(0.0, 2.0, white(), black(), 0.0, 0.5).get_colors().is_empty());
+    }
+
+    #[test]
+    fn pulse_period() {
+        let program = pulse(0.0, 1.75, black(), white(), 1.0);
+
+        assert_eq!(keypoints(&program), vec![
+            (0.0, black(), false), (0.5, white(), true), (1.0, black(), true), (1.5, white(), true),
+            //Halfway from the peak back to the low color
+            (1.75, Color3D::new(128, 128, 128), true),
+        ]);
+        assert_eq!(program.color_at(0.25), Some(Color3D::new(128, 128, 128)));
+    }
+
+    #[test]
+    fn breathe_follows_a_sine_wave() {
+        let program = breathe(0.0, 2.0, black(), white(), 1.0);
+
+        assert_eq!(program.get_colors().len(), 2 * BREATHE_STEPS + 1);
+        assert_eq!(program.color_at(0.0), Some(black()));
+        assert_eq!(program.color_at(0.5), Some(white()));
+        assert_eq!(program.color_at(1.0), Some(black()));
+        assert_eq!(program.color_at(1.25), Some(Color3D::new(128, 128, 128)));
+        assert_eq!(program.color_at(2.0), Some(black()));
+    }
+
+    #[test]
+    fn rainbow_turns_the_hue_wheel() {
+        let program = rainbow(0.0, 6.0, 6.0, 0.0);
+        let colors: Vec<Color3D> = keypoints(&program).iter().map(|keypoint| keypoint.1).collect();
+
+        assert_eq!(program.get_colors().iter().map(|color| color.t).collect::<Vec<_>>(), vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(colors, [HUE_WHEEL.as_slice(), &HUE_WHEEL[..1]].concat().into_iter().map(rgb_color).collect::<Vec<_>>());
+
+        //Half a step ahead, between red and yellow
+        let shifted = rainbow(0.0, 6.0, 6.0, 0.5 / 6.0);
+        assert_eq!(shifted.color_at(0.0), Some(Color3D::new(255, 128, 0)));
+        assert_eq!(shifted.color_at(0.5), Some(Color3D::new(255, 255, 0)));
+        assert_eq!(shifted.color_at(6.0), Some(Color3D::new(255, 128, 0)));
+    }
+
+    #[test]
+    fn sparkle_is_reproducible() {
+        let program = sparkle(0.0, 10.0, black(), white(), 0.1, 0.3, 42);
+
+        assert_eq!(keypoints(&program), keypoints(&sparkle(0.0, 10.0, black(), white(), 0.1, 0.3, 42)));
+        assert_ne!(keypoints(&program), keypoints(&sparkle(0.0, 10.0, black(), white(), 0.1, 0.3, 43)));
+
+        let flashes = program.get_colors().iter().filter(|color| color.as_3d() == white()).count();
+        assert!((15..=45).contains(&flashes), "{} flashes", flashes);
+        assert!(program.get_colors().iter().filter(|color| color.as_3d() == white()).all(|color| program.color_at(color.t + 0.05) == Some(white())));
+    }
+
+    #[test]
+    fn sparkle_density() {
+        assert_eq!(keypoints(&sparkle(0.0, 1.0, black(), white(), 0.1, 0.0, 7)), vec![(0.0, black(), false), (1.0, black(), true)]);
+        assert_eq!(sparkle(0.0, 1.0, black(), white(), 0.25, 1.0, 7).get_colors().len(), 1 + 2 * 4);
+    }
+
+    #[test]
+    fn color_chase_steps_and_phase() {
+        let program = color_chase(0.0, 3.5, &[red(), green(), blue()], 1.0, 1);
+
+        assert_eq!(keypoints(&program), vec![
+            (0.0, green(), false), (1.0, blue(), false), (2.0, red(), false), (3.0, green(), false), (3.5, green(), true),
+        ]);
+        assert!(color_chase(0.0, 3.0, &[], 1.0, 0).get_colors().is_empty());
+    }
+
+    #[test]
+    fn effects_in_tempo() {
+        //120 BPM for the first bar, then 60 BPM
+        let mut map = TempoMap::new(120.0, 4, 4).unwrap();
+        map.set_offset(1.0);
+        map.set_tempo(MusicalPosition::new(2, 1, 0), 60.0).unwrap();
+
+        let program = in_tempo(&map, 2.0, 6.0, |start, end| strobe(start, end, white(), black(), 1.0, 0.5));
+        let times: Vec<f32> = program.get_colors().iter().map(|color| color.t).collect();
+
+        assert_eq!(times, vec![2.0, 2.25, 2.5, 2.75, 3.0, 3.5, 4.0, 4.5, 5.0]);
+        assert_eq!(program.color_at(3.2), Some(white()));
+        assert_eq!(program.color_at(3.7), Some(black()));
+    }
+}
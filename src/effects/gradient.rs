@@ -0,0 +1,217 @@
+use crate::model::color::Color3D;
+use crate::model::light_program::LightProgram;
+use crate::model::plane::{Coordinate3D, Plane};
+use crate::model::trajectory::Trajectory;
+
+/**
+ * Shape of a spatial gradient, i.e. how the position of a drone is mapped to a position
+ * on the color ramp. Positions outside the stops are clamped to the ends of the ramp.
+ */
+#[derive(Clone, Copy)]
+pub enum GradientShape {
+    //0 at origin and 1 at origin + axis, measured along the axis
+    Linear { origin: Coordinate3D, axis: Coordinate3D },
+    //0 at the center and 1 at the given radius, in meters
+    Radial { center: Coordinate3D, radius: f32 },
+    //0 on the plane and 1 at the given distance from it, in meters, on either side
+    PlaneDistance { plane: Plane, distance: f32 },
+}
+
+impl GradientShape {
+    /** Returns the position on the color ramp of a point, without clamping */
+    pub fn ramp_position(&self, point: Coordinate3D) -> f32 {
+        match self {
+            GradientShape::Linear { origin, axis } => {
+                let length_squared = axis[0] * axis[0] + axis[1] * axis[1] + axis[2] * axis[2];
+                if length_squared <= 0.0 { return 0.0 }
+
+                ((point[0] - origin[0]) * axis[0]
+                    + (point[1] - origin[1]) * axis[1]
+                    + (point[2] - origin[2]) * axis[2]) / length_squared
+            },
+            GradientShape::Radial { center, radius } => {
+                if *radius <= 0.0 { return 0.0 }
+
+                let dx = point[0] - center[0];
+                let dy = point[1] - center[1];
+                let dz = point[2] - center[2];
+                (dx * dx + dy * dy + dz * dz).sqrt() / radius
+            },
+            GradientShape::PlaneDistance { plane, distance } => {
+                if *distance <= 0.0 { return 0.0 }
+
+                plane.distance(point).abs() / distance
+            }
+        }
+    }
+}
+
+/**
+ * Effect that colors each drone according to its position in space.
+ */
+pub struct SpatialGradient {
+    shape: GradientShape,
+    //Color stops as (ramp position, color), sorted by position
+    stops: Vec<(f32, Color3D)>,
+    //How far the gradient moves along the ramp per second, to create color sweeps
+    scroll: f32,
+}
+
+impl SpatialGradient {
+    /**
+     * Creates a gradient from its color stops, given in any order.
+     *
+     * Returns:
+     *     the gradient, or an error if the position of a stop or the scroll speed is not finite
+     */
+    pub fn new(shape: GradientShape, stops: Vec<(f32, Color3D)>, scroll: f32) -> Result<Self, &'static str> {
+        if stops.iter().any(|stop| !stop.0.is_finite()) { return Err("SpatialGradient.new: The position of every stop must be finite") }
+        if !scroll.is_finite() { return Err("SpatialGradient.new: The scroll speed must be finite") }
+
+        let mut sorted_stops = stops;
+        sorted_stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Ok(Self { shape, stops: sorted_stops, scroll })
+    }
+
+    /**
+     * Returns the color of a point at the given time, or None if the gradient has no stops
+     * or the point has no position on the ramp (e.g. a coordinate is not finite).
+     *
+     * Parameters:
+     *     point: the position of the drone
+     *     elapsed: seconds since the start of the effect, used to scroll the gradient
+     */
+    pub fn color_at(&self, point: Coordinate3D, elapsed: f32) -> Option<Color3D> {
+        let position = self.shape.ramp_position(point) - self.scroll * elapsed;
+        let (first, last) = (self.stops.first()?, self.stops.last()?);
+        if position.is_nan() { return None }
+
+        if position <= first.0 { return Some(first.1) }
+        if position >= last.0 { return Some(last.1) }
+
+        let index = self.stops.partition_point(|stop| stop.0 <= position);
+        let (from, to) = (self.stops[index - 1], self.stops[index]);
+        let ratio = (position - from.0) / (to.0 - from.0);
+        let (a, b) = (from.1.as_array(), to.1.as_array());
+
+        Some(Color3D::from_array(&[
+            a[0] + ratio * (b[0] - a[0]),
+            a[1] + ratio * (b[1] - a[1]),
+            a[2] + ratio * (b[2] - a[2]),
+            1.0
+        ]))
+    }
+
+    /**
+     * Bakes the gradient into one light program per drone, sampling the position of each
+     * drone at a fixed frame rate. Consecutive samples are faded into each other.
+     *
+     * Parameters:
+     *     trajectories: the trajectory of each drone
+     *     start: start time of the effect, in seconds
+     *     end: end time of the effect, in seconds; use the start time to bake a single frame
+     *     fps: number of samples per second
+     *
+     * Returns:
+     *     the light program of each drone, in the same order as the trajectories
+     */
+    pub fn bake(&self, trajectories: &[Trajectory], start: f32, end: f32, fps: f32) -> Vec<LightProgram> {
+        trajectories.iter()
+            .map(|trajectory| LightProgram::from_function(start, end, fps, |t| {
+                let point = trajectory.position_at(t)?;
+                self.color_at([point.get_x(), point.get_y(), point.get_z()], t - start)
+            }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::point::Point4D;
+
+    fn black() -> Color3D { Color3D::new(0, 0, 0) }
+    fn white() -> Color3D { Color3D::new(255, 255, 255) }
+    fn gray() -> Color3D { Color3D::new(128, 128, 128) }
+
+    fn gradient(shape: GradientShape) -> SpatialGradient {
+        SpatialGradient::new(shape, vec![(1.0, white()), (0.0, black())], 0.0).unwrap()
+    }
+
+    #[test]
+    fn linear_gradient() {
+        let gradient = gradient(GradientShape::Linear { origin: [0.0, 0.0, 0.0], axis: [10.0, 0.0, 0.0] });
+
+        assert_eq!(gradient.color_at([0.0, 0.0, 0.0], 0.0), Some(black()));
+        assert_eq!(gradient.color_at([5.0, 7.0, -3.0], 0.0), Some(gray()));
+        assert_eq!(gradient.color_at([10.0, 0.0, 0.0], 0.0), Some(white()));
+    }
+
+    #[test]
+    fn radial_gradient() {
+        let gradient = gradient(GradientShape::Radial { center: [1.0, 1.0, 1.0], radius: 4.0 });
+
+        assert_eq!(gradient.color_at([1.0, 1.0, 1.0], 0.0), Some(black()));
+        assert_eq!(gradient.color_at([1.0, 3.0, 1.0], 0.0), Some(gray()));
+        assert_eq!(gradient.color_at([1.0, 1.0, -3.0], 0.0), Some(white()));
+    }
+
+    #[test]
+    fn plane_distance_gradient() {
+        let plane = Plane::from_points([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]).unwrap();
+        let gradient = gradient(GradientShape::PlaneDistance { plane, distance: 10.0 });
+
+        assert_eq!(gradient.color_at([3.0, 4.0, 0.0], 0.0), Some(black()));
+        //Both sides of the plane
+        assert_eq!(gradient.color_at([3.0, 4.0, 5.0], 0.0), Some(gray()));
+        assert_eq!(gradient.color_at([3.0, 4.0, -5.0], 0.0), Some(gray()));
+        assert_eq!(gradient.color_at([0.0, 0.0, 20.0], 0.0), Some(white()));
+    }
+
+    #[test]
+    fn stops_are_clamped_at_both_ends() {
+        let shape = GradientShape::Linear { origin: [0.0, 0.0, 0.0], axis: [1.0, 0.0, 0.0] };
+        let red = Color3D::new(255, 0, 0);
+        let gradient = SpatialGradient::new(shape, vec![(0.75, white()), (0.25, red)], 0.0).unwrap();
+
+        assert_eq!(gradient.color_at([-5.0, 0.0, 0.0], 0.0), Some(red));
+        assert_eq!(gradient.color_at([0.25, 0.0, 0.0], 0.0), Some(red));
+        assert_eq!(gradient.color_at([0.5, 0.0, 0.0], 0.0), Some(Color3D::new(255, 128, 128)));
+        assert_eq!(gradient.color_at([0.75, 0.0, 0.0], 0.0), Some(white()));
+        assert_eq!(gradient.color_at([f32::INFINITY, 0.0, 0.0], 0.0), Some(white()));
+    }
+
+    #[test]
+    fn gradient_scrolls_along_the_ramp() {
+        let shape = GradientShape::Linear { origin: [0.0, 0.0, 0.0], axis: [10.0, 0.0, 0.0] };
+        let gradient = SpatialGradient::new(shape, vec![(0.0, black()), (1.0, white())], 0.1).unwrap();
+
+        assert_eq!(gradient.color_at([10.0, 0.0, 0.0], 5.0), Some(gray()));
+    }
+
+    #[test]
+    fn invalid_positions() {
+        let shape = GradientShape::Linear { origin: [0.0, 0.0, 0.0], axis: [10.0, 0.0, 0.0] };
+
+        assert!(SpatialGradient::new(shape, vec![(f32::NAN, black())], 0.0).is_err());
+        assert!(SpatialGradient::new(shape, vec![(0.0, black())], f32::INFINITY).is_err());
+        assert_eq!(gradient(shape).color_at([f32::NAN, 0.0, 0.0], 0.0), None);
+        assert_eq!(SpatialGradient::new(shape, Vec::new(), 0.0).unwrap().color_at([0.0, 0.0, 0.0], 0.0), None);
+    }
+
+    #[test]
+    fn bake_samples_each_drone() {
+        let gradient = gradient(GradientShape::Linear { origin: [0.0, 0.0, 0.0], axis: [10.0, 0.0, 0.0] });
+        let trajectories = vec![
+            Trajectory::new(vec![Point4D::new(0.0, 0.0, 0.0, 5.0), Point4D::new(2.0, 10.0, 0.0, 5.0)]),
+            Trajectory::new(vec![Point4D::new(0.0, 10.0, 0.0, 5.0)]),
+        ];
+        let programs = gradient.bake(&trajectories, 0.0, 2.0, 2.0);
+
+        assert_eq!(programs[0].get_colors().iter().map(|color| color.t).collect::<Vec<_>>(), vec![0.0, 0.5, 1.0, 1.5, 2.0]);
+        assert_eq!(programs[0].color_at(1.0), Some(gray()));
+        assert_eq!(programs[0].color_at(2.0), Some(white()));
+        assert!(programs[1].get_colors().iter().all(|color| color.as_3d() == white()));
+    }
+}
@@ -0,0 +1,222 @@
+use crate::colors::colors::BlendMode;
+use crate::model::color::{Color3D, Color4D};
+use crate::model::light_program::LightProgram;
+use crate::utils::frame_times;
+
+/**
+ * A single light effect of a drone placed on the show timeline, together with the way it
+ * is composited over the layers below it.
+ */
+#[derive(Clone)]
+pub struct LightLayer {
+    program: LightProgram,
+    mode: BlendMode,
+    //Opacity of the layer in the range [0-1]
+    opacity: f32,
+    //The layer is active from start (included) to end (excluded), in seconds
+    start: f32,
+    end: f32,
+}
+
+impl LightLayer {
+    pub fn new(program: LightProgram, mode: BlendMode, opacity: f32, start: f32, end: f32) -> Self {
+        Self { program, mode, opacity: opacity.clamp(0.0, 1.0), start, end }
+    }
+
+    pub fn is_active(&self, t: f32) -> bool {
+        t >= self.start && t < self.end
+    }
+}
+
+/**
+ * Stack of light layers of a single drone, composited from bottom to top over a black
+ * backdrop, similar to the light effects panel of Skybrush Studio.
+ */
+#[derive(Clone)]
+pub struct LayerStack {
+    //Layers from bottom to top
+    layers: Vec<LightLayer>
+}
+
+impl LayerStack {
+    pub fn new() -> Self {
+        Self { layers: Vec::new() }
+    }
+
+    pub fn new_from(layers: Vec<LightLayer>) -> Self {
+        Self { layers }
+    }
+
+    /** Add a layer on top of the stack */
+    pub fn push(&mut self, layer: LightLayer) {
+        self.layers.push(layer);
+    }
+
+    pub fn get_layers(&self) -> &[LightLayer] {
+        &self.layers
+    }
+
+    /**
+     * Composites every layer active at the given time and returns the resulting Blender
+     * color array
+     */
+    pub fn array_at(&self, t: f32) -> [f32; 4] {
+        self.composite(t, |layer| layer.is_active(t))
+    }
+
+    /* Composites the layers for which is_active returns true at the given time */
+    fn composite(&self, t: f32, is_active: impl Fn(&LightLayer) -> bool) -> [f32; 4] {
+        let mut backdrop = [0.0, 0.0, 0.0, 1.0];
+
+        for layer in self.layers.iter().filter(|layer| is_active(layer)) {
+            if let Some(color) = layer.program.array_at(t) {
+                let source = [color[0], color[1], color[2], layer.opacity];
+                BlendMode::blend_in_place(&source, &mut backdrop, layer.mode);
+            }
+        }
+
+        backdrop
+    }
+
+    /**
+     * Flattens the stack into a single light program.
+     *
+     * The composite is sampled at a fixed frame rate, at every keypoint of the layers and
+     * at the boundaries of every layer. Layer boundaries and abrupt keypoints of the layers
+     * become abrupt changes in the result; every other sample fades from the previous one.
+     * The sample at the end includes the layers that end right there, so they last until
+     * the end of the program.
+     *
+     * Parameters:
+     *     start: start time of the flattened program, in seconds
+     *     end: end time of the flattened program, in seconds
+     *     fps: number of samples per second between keypoints
+     */
+    pub fn flatten(&self, start: f32, end: f32, fps: f32) -> LightProgram {
+        //Sample times, each with a flag telling whether the composite may change abruptly there
+        let mut events: Vec<(f32, bool)> = Vec::new();
+
+        events.extend(frame_times(start, end, fps).into_iter().map(|t| (t, false)));
+        events.push((end, false));
+        for layer in &self.layers {
+            events.push((layer.start, true));
+            //Layers that end with the program are kept in its last sample, see below
+            events.push((layer.end, layer.end != end));
+            for color in layer.program.get_colors() {
+                if layer.is_active(color.t) {
+                    events.push((color.t, !color.is_fade()));
+                }
+            }
+        }
+        events.retain(|(t, _)| *t >= start && *t <= end);
+        events.sort_by(|a, b| a.0.total_cmp(&b.0));
+        events.dedup_by(|next, previous| {
+            let is_same = next.0 == previous.0;
+            if is_same { previous.1 |= next.1 }
+            is_same
+        });
+
+        let colors: Vec<Color4D> = events.iter()
+            .enumerate()
+            .map(|(index, (t, is_abrupt))| {
+                //The end of the program is included, unlike the end of the layers
+                let color = if *t == end {
+                    self.composite(*t, |layer| layer.start < layer.end && layer.start <= *t && *t <= layer.end)
+                } else {
+                    self.array_at(*t)
+                };
+                Color3D::from_array(&color).at_time(*t, index > 0 && !is_abrupt)
+            })
+            .collect();
+
+        LightProgram::new(colors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(rgb: [u8; 3]) -> LightProgram {
+        LightProgram::new(vec![Color3D::new(rgb[0], rgb[1], rgb[2]).at_time(0.0, false)])
+    }
+
+    fn rgb(color: [f32; 4]) -> Color3D {
+        Color3D::from_array(&color)
+    }
+
+    #[test]
+    fn empty_stack_is_black() {
+        assert_eq!(LayerStack::new().array_at(1.0), [0.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn opacity() {
+        let stack = LayerStack::new_from(vec![LightLayer::new(solid([255, 255, 255]), BlendMode::NORMAL, 0.5, 0.0, 10.0)]);
+        assert_eq!(rgb(stack.array_at(1.0)), Color3D::new(128, 128, 128));
+
+        //Opacity is clamped to [0-1]
+        let stack = LayerStack::new_from(vec![LightLayer::new(solid([255, 0, 0]), BlendMode::NORMAL, 2.0, 0.0, 10.0)]);
+        assert_eq!(rgb(stack.array_at(1.0)), Color3D::new(255, 0, 0));
+    }
+
+    #[test]
+    fn layers_are_blended_from_bottom_to_top() {
+        let white = LightLayer::new(solid([255, 255, 255]), BlendMode::NORMAL, 1.0, 0.0, 10.0);
+        let red = LightLayer::new(solid([255, 0, 0]), BlendMode::MULTIPLY, 1.0, 0.0, 10.0);
+        let blue = LightLayer::new(solid([0, 0, 255]), BlendMode::NORMAL, 1.0, 0.0, 10.0);
+
+        let stack = LayerStack::new_from(vec![white.clone(), red.clone()]);
+        assert_eq!(rgb(stack.array_at(1.0)), Color3D::new(255, 0, 0));
+
+        //Multiplying the black backdrop first keeps it black, then white covers it
+        let stack = LayerStack::new_from(vec![red.clone(), white]);
+        assert_eq!(rgb(stack.array_at(1.0)), Color3D::new(255, 255, 255));
+
+        let mut stack = LayerStack::new_from(vec![blue.clone()]);
+        stack.push(red);
+        assert_eq!(rgb(stack.array_at(1.0)), Color3D::new(0, 0, 0));
+        stack.push(blue);
+        assert_eq!(rgb(stack.array_at(1.0)), Color3D::new(0, 0, 255));
+    }
+
+    #[test]
+    fn layers_are_active_from_start_to_end() {
+        let stack = LayerStack::new_from(vec![LightLayer::new(solid([255, 0, 0]), BlendMode::NORMAL, 1.0, 1.0, 2.0)]);
+
+        assert_eq!(rgb(stack.array_at(0.99)), Color3D::new(0, 0, 0));
+        assert_eq!(rgb(stack.array_at(1.0)), Color3D::new(255, 0, 0));
+        assert_eq!(rgb(stack.array_at(2.0)), Color3D::new(0, 0, 0));
+    }
+
+    #[test]
+    fn flatten_changes_abruptly_at_layer_boundaries() {
+        let stack = LayerStack::new_from(vec![LightLayer::new(solid([255, 0, 0]), BlendMode::NORMAL, 1.0, 1.0, 2.0)]);
+        let program = stack.flatten(0.0, 3.0, 1.0);
+        let keypoints: Vec<(f32, Color3D, bool)> = program.get_colors().iter().map(|color| (color.t, color.as_3d(), color.is_fade())).collect();
+
+        assert_eq!(keypoints, vec![
+            (0.0, Color3D::new(0, 0, 0), false),
+            (1.0, Color3D::new(255, 0, 0), false),
+            (2.0, Color3D::new(0, 0, 0), false),
+            (3.0, Color3D::new(0, 0, 0), true),
+        ]);
+    }
+
+    #[test]
+    fn flatten_keeps_layers_ending_with_the_program() {
+        let fade = LightProgram::new(vec![
+            Color3D::new(0, 0, 0).at_time(0.0, false),
+            Color3D::new(0, 0, 255).at_time(2.0, true),
+        ]);
+        let stack = LayerStack::new_from(vec![LightLayer::new(fade, BlendMode::NORMAL, 1.0, 0.0, 2.0)]);
+        let program = stack.flatten(0.0, 2.0, 4.0);
+        let last = program.get_colors().last().unwrap();
+
+        assert_eq!(last.t, 2.0);
+        assert_eq!(last.as_3d(), Color3D::new(0, 0, 255));
+        assert!(last.is_fade());
+        assert_eq!(program.color_at(1.0), Some(Color3D::new(0, 0, 128)));
+        assert_eq!(program.color_at(1.9), Some(Color3D::new(0, 0, 242)));
+    }
+}
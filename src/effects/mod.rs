@@ -0,0 +1,4 @@
+pub mod generators;
+pub mod gradient;
+pub mod layers;
+pub mod projection;
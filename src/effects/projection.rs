@@ -0,0 +1,168 @@
+use crate::model::cameras::Camera;
+use crate::model::color::Color3D;
+use crate::model::image::Image;
+use crate::model::light_program::LightProgram;
+use crate::model::plane::Coordinate3D;
+use crate::model::trajectory::Trajectory;
+
+/**
+ * Effect that paints an image or a video clip onto the formation as seen from a camera,
+ * like a projector placed where the audience is.
+ */
+pub struct ImageProjection {
+    camera: Camera,
+    //Frames of the clip in playback order; a single frame projects a still image
+    frames: Vec<Image>,
+    //Number of frames of the clip per second
+    frame_rate: f32,
+    //Color of the drones outside the image or behind the camera, also shown through transparent pixels
+    background: Color3D,
+}
+
+impl ImageProjection {
+    pub fn new(camera: Camera, frames: Vec<Image>, frame_rate: f32, background: Color3D) -> Self {
+        Self { camera, frames, frame_rate, background }
+    }
+
+    /** Creates a projection of a still image */
+    pub fn new_from_image(camera: Camera, image: Image, background: Color3D) -> Self {
+        Self { camera, frames: vec![image], frame_rate: 0.0, background }
+    }
+
+    /**
+     * Returns the frame of the clip shown after the given number of seconds. The last frame
+     * is held once the clip is over.
+     */
+    pub fn frame_at(&self, elapsed: f32) -> Option<&Image> {
+        let index = if self.frame_rate > 0.0 && elapsed > 0.0 { (elapsed * self.frame_rate) as usize } else { 0 };
+        self.frames.get(index.min(self.frames.len().saturating_sub(1)))
+    }
+
+    /**
+     * Returns the color of a point at the given time.
+     *
+     * Parameters:
+     *     point: the position of the drone
+     *     elapsed: seconds since the start of the effect, used to pick the frame of the clip
+     */
+    pub fn color_at(&self, point: Coordinate3D, elapsed: f32) -> Color3D {
+        let sampled = self.frame_at(elapsed).and_then(|frame| {
+            let [u, v] = self.camera.project(point, frame.aspect())?;
+            frame.sample(u, v, self.background)
+        });
+
+        sampled.unwrap_or(self.background)
+    }
+
+    /**
+     * Bakes the projection into one light program per drone, sampling the position of each
+     * drone at a fixed frame rate. Consecutive samples are faded into each other.
+     *
+     * Parameters:
+     *     trajectories: the trajectory of each drone
+     *     start: start time of the effect, in seconds
+     *     end: end time of the effect, in seconds; use the start time to bake a single frame
+     *     fps: number of samples per second
+     *
+     * Returns:
+     *     the light program of each drone, in the same order as the trajectories
+     */
+    pub fn bake(&self, trajectories: &[Trajectory], start: f32, end: f32, fps: f32) -> Vec<LightProgram> {
+        trajectories.iter()
+            .map(|trajectory| LightProgram::from_function(start, end, fps, |t| {
+                let point = trajectory.position_at(t)?;
+                Some(self.color_at([point.get_x(), point.get_y(), point.get_z()], t - start))
+            }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::point::Point4D;
+
+    fn black() -> Color3D { Color3D::new(0, 0, 0) }
+    fn red() -> Color3D { Color3D::new(255, 0, 0) }
+    fn green() -> Color3D { Color3D::new(0, 255, 0) }
+    fn blue() -> Color3D { Color3D::new(0, 0, 255) }
+
+    /* Camera at the origin looking down -Z, so that the image spans x and y in [-depth, depth] */
+    fn camera() -> Camera {
+        Camera::new_from("Camera".to_string(), [0.0, 0.0, 0.0], [1.0, 0.0, 0.0, 0.0], 90.0)
+    }
+
+    /* 2x2 opaque image: red and green on top, blue and white at the bottom */
+    fn image() -> Image {
+        Image::new(2, 2, vec![[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [255, 255, 255, 255]]).unwrap()
+    }
+
+    fn solid(color: [u8; 4]) -> Image {
+        Image::new(1, 1, vec![color]).unwrap()
+    }
+
+    #[test]
+    fn projects_each_quadrant_of_the_image() {
+        let projection = ImageProjection::new_from_image(camera(), image(), black());
+
+        assert_eq!(projection.color_at([-5.0, 5.0, -10.0], 0.0), red());
+        assert_eq!(projection.color_at([5.0, 5.0, -10.0], 0.0), green());
+        assert_eq!(projection.color_at([-5.0, -5.0, -10.0], 0.0), blue());
+        assert_eq!(projection.color_at([5.0, -5.0, -10.0], 0.0), Color3D::new(255, 255, 255));
+        //Farther points are scaled down towards the center of the image
+        assert_eq!(projection.color_at([-5.0, 5.0, -100.0], 0.0), red());
+    }
+
+    #[test]
+    fn points_out_of_frame_or_behind_the_camera_show_the_background() {
+        let projection = ImageProjection::new_from_image(camera(), image(), blue());
+
+        assert_eq!(projection.color_at([15.0, 0.0, -10.0], 0.0), blue());
+        assert_eq!(projection.color_at([0.0, -15.0, -10.0], 0.0), blue());
+        //Mirrored through the camera, these points would land on the red and green pixels
+        assert_eq!(projection.color_at([5.0, -5.0, 10.0], 0.0), blue());
+        assert_eq!(projection.color_at([-5.0, -5.0, 10.0], 0.0), blue());
+    }
+
+    #[test]
+    fn transparent_pixels_show_the_background() {
+        let projection = ImageProjection::new_from_image(camera(), solid([255, 0, 0, 0]), green());
+
+        assert_eq!(projection.color_at([0.0, 0.0, -10.0], 0.0), green());
+    }
+
+    #[test]
+    fn frame_at_plays_the_clip_and_holds_the_last_frame() {
+        let frames = vec![solid([255, 0, 0, 255]), solid([0, 255, 0, 255]), solid([0, 0, 255, 255])];
+        let projection = ImageProjection::new(camera(), frames, 2.0, black());
+
+        assert_eq!(projection.color_at([0.0, 0.0, -10.0], -1.0), red());
+        assert_eq!(projection.color_at([0.0, 0.0, -10.0], 0.0), red());
+        assert_eq!(projection.color_at([0.0, 0.0, -10.0], 0.5), green());
+        assert_eq!(projection.color_at([0.0, 0.0, -10.0], 1.25), blue());
+        assert_eq!(projection.color_at([0.0, 0.0, -10.0], 60.0), blue());
+    }
+
+    #[test]
+    fn empty_clip_shows_the_background() {
+        let projection = ImageProjection::new(camera(), Vec::new(), 24.0, green());
+
+        assert!(projection.frame_at(0.0).is_none());
+        assert_eq!(projection.color_at([0.0, 0.0, -10.0], 0.0), green());
+    }
+
+    #[test]
+    fn bake_samples_each_drone() {
+        let projection = ImageProjection::new_from_image(camera(), image(), black());
+        let trajectories = vec![
+            Trajectory::new(vec![Point4D::new(0.0, -5.0, 5.0, -10.0)]),
+            //Flies through the camera, ending behind it
+            Trajectory::new(vec![Point4D::new(0.0, -5.0, -5.0, -10.0), Point4D::new(2.0, -5.0, -5.0, 10.0)]),
+        ];
+        let programs = projection.bake(&trajectories, 0.0, 2.0, 1.0);
+
+        assert!(programs[0].get_colors().iter().all(|color| color.as_3d() == red()));
+        let colors: Vec<(f32, Color3D)> = programs[1].get_colors().iter().map(|color| (color.t, color.as_3d())).collect();
+        assert_eq!(colors, vec![(0.0, blue()), (1.0, black()), (2.0, black())]);
+    }
+}
@@ -1,5 +1,8 @@
+mod audio;
 pub mod colors;
+mod effects;
 mod model;
+mod safety;
 mod utils;
 fn main() {
     println!("hola soy  Ana Maria");
@@ -1,17 +1,191 @@
+use super::plane::Coordinate3D;
+
+//Horizontal field of view of the default Blender camera (50 mm lens on a 36 mm sensor), in degrees
+const DEFAULT_FOV: f32 = 39.6;
+
 /**
  * Struct representing a single camera in the scene.
- * 
+ *
  * This struct is a simplified representation of the properties that a typical
  * camera may have in a real 3D software like Blender (aplicacion de graficos)
  */
-struct Camera {
+#[derive(Clone)]
+pub struct Camera {
     name: String, //The name of the camera
     position: [f32; 3], //The position of the camera in 3D space
     orientation: [f32; 4], //The orientation of the camera using Blender quaternions
+    fov: f32, //The horizontal field of view of the camera, in degrees
 }
 
 impl Camera {
     pub fn new(name: String, position: [f32; 3], orientation: [f32; 4]) -> Self {
-        Self { name: name, position: position, orientation: orientation }
+        Self { name: name, position: position, orientation: orientation, fov: DEFAULT_FOV }
+    }
+
+    pub fn new_from(name: String, position: [f32; 3], orientation: [f32; 4], fov: f32) -> Self {
+        Self { name, position, orientation, fov }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_position(&self) -> [f32; 3] {
+        self.position
+    }
+
+    pub fn get_orientation(&self) -> [f32; 4] {
+        self.orientation
+    }
+
+    pub fn get_fov(&self) -> f32 {
+        self.fov
+    }
+
+    /**
+     * Projects a point onto the image plane of the camera, following the Blender convention
+     * where a camera without rotation looks down the -Z axis with +Y pointing up.
+     *
+     * Parameters:
+     *     point: the point to project, in world coordinates
+     *     aspect: the width of the image divided by its height
+     *
+     * Returns:
+     *     the position of the point on the image as [u, v], where (0, 0) is the top left
+     *     corner and (1, 1) the bottom right one, or None if the point is not in front of the
+     *     camera. Points outside the field of view are projected outside the [0-1] range.
+     */
+    pub fn project(&self, point: Coordinate3D, aspect: f32) -> Option<[f32; 2]> {
+        let half_width = (self.fov.to_radians() / 2.0).tan();
+        if half_width <= 0.0 || aspect <= 0.0 { return None }
+
+        let relative = [
+            point[0] - self.position[0],
+            point[1] - self.position[1],
+            point[2] - self.position[2]
+        ];
+        let local = self.to_local(relative);
+        let depth = -local[2];
+        if depth <= 0.0 { return None }
+
+        let x = local[0] / (depth * half_width);
+        let y = local[1] / (depth * half_width / aspect);
+        Some([0.5 + x / 2.0, 0.5 - y / 2.0])
+    }
+
+    /* Rotates a vector from world axes to the axes of the camera, i.e. by the inverse orientation */
+    fn to_local(&self, vector: Coordinate3D) -> Coordinate3D {
+        let [w, x, y, z] = self.orientation;
+        let norm = (w * w + x * x + y * y + z * z).sqrt();
+        if norm <= 0.0 { return vector }
+
+        //The inverse of a unit quaternion is its conjugate
+        let (w, q) = (w / norm, [-x / norm, -y / norm, -z / norm]);
+        let cross = |a: Coordinate3D, b: Coordinate3D| [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0]
+        ];
+
+        let c = cross(q, vector);
+        let t = [2.0 * c[0], 2.0 * c[1], 2.0 * c[2]];
+        let u = cross(q, t);
+        [
+            vector[0] + w * t[0] + u[0],
+            vector[1] + w * t[1] + u[1],
+            vector[2] + w * t[2] + u[2]
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* Camera at the origin with a 90 degree field of view, so the edges of the image are at x = ±depth */
+    fn camera(orientation: [f32; 4]) -> Camera {
+        Camera::new_from("Camera".to_string(), [0.0, 0.0, 0.0], orientation, 90.0)
+    }
+
+    fn assert_projects(projected: Option<[f32; 2]>, expected: [f32; 2]) {
+        let [u, v] = projected.expect("the point should be in front of the camera");
+        assert!((u - expected[0]).abs() < 1e-5 && (v - expected[1]).abs() < 1e-5, "{:?} != {:?}", [u, v], expected);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn default_camera_looks_down_negative_z() {
+        let camera = camera([1.0, 0.0, 0.0, 0.0]);
+
+        assert_projects(camera.project([0.0, 0.0, -10.0], 1.0), [0.5, 0.5]);
+        assert_projects(camera.project([10.0, 0.0, -10.0], 1.0), [1.0, 0.5]);
+        assert_projects(camera.project([-5.0, 5.0, -10.0], 1.0), [0.25, 0.25]);
+        assert_projects(camera.project([0.0, -10.0, -10.0], 1.0), [0.5, 1.0]);
+    }
+
+    #[test]
+    fn aspect_narrows_vertical_field_of_view() {
+        let camera = camera([1.0, 0.0, 0.0, 0.0]);
+
+        assert_projects(camera.project([10.0, 5.0, -10.0], 2.0), [1.0, 0.0]);
+        assert_projects(camera.project([0.0, 2.5, -10.0], 2.0), [0.5, 0.25]);
+    }
+
+    #[test]
+    fn points_out_of_frame_project_outside_the_image() {
+        let camera = camera([1.0, 0.0, 0.0, 0.0]);
+
+        assert_projects(camera.project([20.0, 0.0, -10.0], 1.0), [1.5, 0.5]);
+        assert_projects(camera.project([0.0, 30.0, -10.0], 1.0), [0.5, -1.0]);
+    }
+
+    #[test]
+    fn points_behind_the_camera_are_rejected() {
+        let camera = camera([1.0, 0.0, 0.0, 0.0]);
+
+        //A mirrored projection would put these points back inside the image
+        assert_eq!(camera.project([0.0, 0.0, 10.0], 1.0), None);
+        assert_eq!(camera.project([5.0, -5.0, 10.0], 1.0), None);
+        assert_eq!(camera.project([3.0, 0.0, 0.0], 1.0), None);
+    }
+
+    #[test]
+    fn rotated_camera() {
+        //Rotated 90 degrees around X, as the front camera in Blender: looks down +Y with +Z up
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        let camera = camera([half, half, 0.0, 0.0]);
+
+        assert_projects(camera.project([0.0, 10.0, 0.0], 1.0), [0.5, 0.5]);
+        assert_projects(camera.project([5.0, 10.0, 5.0], 1.0), [0.75, 0.25]);
+        assert_eq!(camera.project([0.0, 0.0, -10.0], 1.0), None);
+        assert_eq!(camera.project([0.0, -10.0, 0.0], 1.0), None);
+    }
+
+    #[test]
+    fn orientation_is_normalized() {
+        let scaled = camera([2.0, 2.0, 0.0, 0.0]);
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        let unit = camera([half, half, 0.0, 0.0]);
+
+        assert_projects(scaled.project([5.0, 10.0, 5.0], 1.0), unit.project([5.0, 10.0, 5.0], 1.0).unwrap());
+    }
+
+    #[test]
+    fn projection_is_relative_to_the_camera_position() {
+        let camera = Camera::new_from("Camera".to_string(), [10.0, 0.0, 5.0], [1.0, 0.0, 0.0, 0.0], 90.0);
+
+        assert_projects(camera.project([10.0, 0.0, -5.0], 1.0), [0.5, 0.5]);
+        assert_projects(camera.project([20.0, 0.0, -5.0], 1.0), [1.0, 0.5]);
+        assert_eq!(camera.project([0.0, 0.0, 0.0], 1.0).map(|[_, v]| v), Some(0.5));
+        assert_eq!(camera.project([10.0, 0.0, 10.0], 1.0), None);
+    }
+
+    #[test]
+    fn invalid_field_of_view_or_aspect() {
+        let camera = Camera::new_from("Camera".to_string(), [0.0, 0.0, 0.0], [1.0, 0.0, 0.0, 0.0], 0.0);
+        assert_eq!(camera.project([0.0, 0.0, -10.0], 1.0), None);
+
+        let camera = Camera::new("Camera".to_string(), [0.0, 0.0, 0.0], [1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(camera.get_fov(), DEFAULT_FOV);
+        assert_eq!(camera.project([0.0, 0.0, -10.0], 0.0), None);
+    }
+}
@@ -1,7 +1,12 @@
+use crate::colors::colors::ColorSpace;
+
+use super::easing::Easing;
+
 /**
  * Simplest representation of a 3D color in RGB space.
  */
-struct Color3D {
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Color3D {
     //Red component of the color in the range [0-255]
     r: u8,
     //Green component of the color in the range [0-255]
@@ -15,6 +20,62 @@ impl Color3D {
         Self { r, g, b }
     }
 
+    /**
+     * Creates a color from a Blender color array, ignoring the alpha channel. Channels are
+     * clamped to the [0-1] range and rounded to the nearest integer value.
+     */
+    pub fn from_array(color: &[f32; 4]) -> Self {
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Self { r: channel(color[0]), g: channel(color[1]), b: channel(color[2]) }
+    }
+
+    /**
+     * Creates a color from its coordinates in the given color space. See as_space().
+     */
+    pub fn from_space(space: ColorSpace, color: &[f32; 3]) -> Self {
+        let rgb = space.to_rgb(color);
+        Self::from_array(&[rgb[0], rgb[1], rgb[2], 1.0])
+    }
+
+    /**
+     * Returns the coordinates of the color in the given color space: RGB channels in [0-1],
+     * HSV and HSL as [hue in degrees, saturation, value or lightness], or OKLab as [L, a, b].
+     */
+    pub fn as_space(&self, space: ColorSpace) -> [f32; 3] {
+        let rgb = self.as_array();
+        space.from_rgb(&[rgb[0], rgb[1], rgb[2]])
+    }
+
+    pub fn as_hsv(&self) -> [f32; 3] {
+        self.as_space(ColorSpace::HSV)
+    }
+    pub fn as_hsl(&self) -> [f32; 3] {
+        self.as_space(ColorSpace::HSL)
+    }
+    pub fn as_oklab(&self) -> [f32; 3] {
+        self.as_space(ColorSpace::OKLAB)
+    }
+
+    pub fn from_hsv(hsv: &[f32; 3]) -> Self {
+        Self::from_space(ColorSpace::HSV, hsv)
+    }
+    pub fn from_hsl(hsl: &[f32; 3]) -> Self {
+        Self::from_space(ColorSpace::HSL, hsl)
+    }
+    pub fn from_oklab(lab: &[f32; 3]) -> Self {
+        Self::from_space(ColorSpace::OKLAB, lab)
+    }
+
+    pub fn get_r(&self) -> u8 {
+        self.r
+    }
+    pub fn get_g(&self) -> u8 {
+        self.g
+    }
+    pub fn get_b(&self) -> u8 {
+        self.b
+    }
+
     /**
      * Returns a Color4D copy of this color such that the copy is placed
      * at the given number of seconds on the time axis.
@@ -26,7 +87,7 @@ impl Color3D {
      *              abruptly (False)
      */
     pub fn at_time(&self, t: f32, is_fade: bool) -> Color4D {
-        Color4D { t, r: self.r, g: self.g, b: self.b, is_fade }
+        Color4D { t, r: self.r, g: self.g, b: self.b, is_fade, easing: Easing::LINEAR }
     }
     /** Convert a Color3D instante to a Blender color array with alpha channel included */
     pub fn as_array(&self) -> [f32; 4] {
@@ -37,7 +98,7 @@ impl Color3D {
 /**
  * Simplest representation of a 4D color in RGB space and time.
  */
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Color4D {
     pub t: f32,
     pub r: u8,
@@ -45,14 +106,57 @@ pub struct Color4D {
     pub b: u8,
     /* flag to specify wheter we should fade here from the previous keypoint (True) or maintain
     previous color until this moment and change here abruptly (False) */
-    is_fade: bool
+    is_fade: bool,
+    //Timing curve of the fade from the previous keypoint, ignored if is_fade is false
+    easing: Easing
 }
 
 impl Color4D {
+    /**
+     * Returns a Color3D instance with the same RGB components as this instance
+     */
+    pub fn as_3d(&self) -> Color3D {
+        Color3D { r: self.r, g: self.g, b: self.b }
+    }
+
     /** Return a Color4D instante to a Blender color vector with alpha channel
      * included and ingnoring the timestamp
      */
     pub fn as_array(&self) -> [f32; 4] {
         [self.r as f32 / 255.0, self.g as f32 / 255.0, self.b as f32 / 255.0, 1.0]
     }
+
+    /** Return the coordinates of the color in the given color space, ignoring the timestamp */
+    pub fn as_space(&self, space: ColorSpace) -> [f32; 3] {
+        self.as_3d().as_space(space)
+    }
+
+    pub fn as_hsv(&self) -> [f32; 3] {
+        self.as_space(ColorSpace::HSV)
+    }
+    pub fn as_hsl(&self) -> [f32; 3] {
+        self.as_space(ColorSpace::HSL)
+    }
+    pub fn as_oklab(&self) -> [f32; 3] {
+        self.as_space(ColorSpace::OKLAB)
+    }
+
+    /** Return whether the color is reached fading from the previous keypoint */
+    pub fn is_fade(&self) -> bool {
+        self.is_fade
+    }
+
+    pub fn get_easing(&self) -> Easing {
+        self.easing
+    }
+
+    /** Returns a copy of this keypoint that reaches its color with the given timing curve */
+    pub fn with_easing(&self, easing: Easing) -> Color4D {
+        Color4D { easing, ..*self }
+    }
+
+    /** Returns a copy of this keypoint placed at the given number of seconds */
+    pub fn with_time(&self, t: f32) -> Color4D {
+        Color4D { t, ..*self }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,69 @@
+//Iterations used to invert the x coordinate of a cubic Bézier curve
+const BEZIER_ITERATIONS: usize = 24;
+
+/**
+ * Timing curve of a fade, mapping the elapsed fraction of the fade to the fraction of the
+ * color change, both in the range [0-1].
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Easing {
+    LINEAR,
+    //Same curves as the CSS ease-in, ease-out and ease-in-out timing functions
+    EASE_IN,
+    EASE_OUT,
+    EASE_IN_OUT,
+    //Cubic Bézier curve from (0, 0) to (1, 1) with control points (x1, y1) and (x2, y2)
+    CUBIC_BEZIER(f32, f32, f32, f32),
+    //Changes abruptly in the given number of equal steps, at the end of each step
+    STEPS(u32),
+}
+
+impl Easing {
+    /**
+     * Returns the fraction of the color change reached when the given fraction of the fade
+     * has elapsed.
+     */
+    pub fn apply(&self, ratio: f32) -> f32 {
+        let ratio = ratio.clamp(0.0, 1.0);
+
+        match *self {
+            Easing::LINEAR => ratio,
+            Easing::EASE_IN => cubic_bezier(0.42, 0.0, 1.0, 1.0, ratio),
+            Easing::EASE_OUT => cubic_bezier(0.0, 0.0, 0.58, 1.0, ratio),
+            Easing::EASE_IN_OUT => cubic_bezier(0.42, 0.0, 0.58, 1.0, ratio),
+            Easing::CUBIC_BEZIER(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, ratio),
+            Easing::STEPS(steps) => {
+                if steps == 0 { return ratio }
+                (ratio * steps as f32).floor() / steps as f32
+            }
+        }
+    }
+
+    pub fn is_linear(&self) -> bool {
+        *self == Easing::LINEAR
+    }
+}
+
+/*
+ * Evaluates the y coordinate of a cubic Bézier timing curve at the given x coordinate. The
+ * x coordinates of the control points are clamped to [0-1] so the curve is a function of x.
+ */
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    if x <= 0.0 { return 0.0 }
+    if x >= 1.0 { return 1.0 }
+
+    let (x1, x2) = (x1.clamp(0.0, 1.0), x2.clamp(0.0, 1.0));
+    let coordinate = |p1: f32, p2: f32, s: f32| {
+        let inverse = 1.0 - s;
+        3.0 * inverse * inverse * s * p1 + 3.0 * inverse * s * s * p2 + s * s * s
+    };
+
+    //x(s) is monotonic in [0, 1], so bisection always converges
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..BEZIER_ITERATIONS {
+        let middle = (low + high) / 2.0;
+        if coordinate(x1, x2, middle) < x { low = middle } else { high = middle }
+    }
+
+    coordinate(y1, y2, (low + high) / 2.0)
+}
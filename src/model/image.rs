@@ -0,0 +1,242 @@
+use std::fs::File;
+use std::path::Path;
+
+use super::color::Color3D;
+
+/**
+ * Simple RGBA raster image, used to project pictures and video frames onto formations.
+ */
+#[derive(Clone)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    //Pixels in rows from top to bottom, as [r, g, b, alpha] in the range [0-255]
+    pixels: Vec<[u8; 4]>
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, pixels: Vec<[u8; 4]>) -> Result<Self, &'static str> {
+        if pixels.len() != width * height {
+            return Err("Image.new: The number of pixels doesn't match the size of the image");
+        }
+
+        Ok(Self { width, height, pixels })
+    }
+
+    /**
+     * Reads an image from a PNG file. Every color type and bit depth is converted to 8-bit
+     * RGBA; images without alpha channel are fully opaque.
+     */
+    pub fn from_png(path: &Path) -> Result<Self, &'static str> {
+        let file = File::open(path).map_err(|_| "Image.from_png: Can't open the file")?;
+        let mut decoder = png::Decoder::new(file);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(|_| "Image.from_png: The file is not a valid PNG image")?;
+
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).map_err(|_| "Image.from_png: Can't decode the PNG image")?;
+        let bytes = &buffer[..info.buffer_size()];
+
+        let pixels: Vec<[u8; 4]> = match info.color_type {
+            png::ColorType::Rgba => bytes.chunks_exact(4).map(|p| [p[0], p[1], p[2], p[3]]).collect(),
+            png::ColorType::Rgb => bytes.chunks_exact(3).map(|p| [p[0], p[1], p[2], 255]).collect(),
+            png::ColorType::GrayscaleAlpha => bytes.chunks_exact(2).map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+            png::ColorType::Grayscale => bytes.iter().map(|p| [*p, *p, *p, 255]).collect(),
+            png::ColorType::Indexed => return Err("Image.from_png: Indexed images are not supported")
+        };
+
+        Image::new(info.width as usize, info.height as usize, pixels)
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    /** Return the pixel at the given column and row as [r, g, b, alpha], if inside the image */
+    pub fn pixel(&self, x: usize, y: usize) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height { return None }
+
+        Some(self.pixels[y * self.width + x])
+    }
+
+    /**
+     * Samples the nearest pixel to a position on the image and composites it over a
+     * background color according to its alpha channel.
+     *
+     * Parameters:
+     *     u: horizontal position, from 0 at the left edge to 1 at the right one
+     *     v: vertical position, from 0 at the top edge to 1 at the bottom one
+     *     background: the color shown through transparent pixels
+     *
+     * Returns:
+     *     the color at the position, or None if the position is outside the image
+     */
+    pub fn sample(&self, u: f32, v: f32, background: Color3D) -> Option<Color3D> {
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) { return None }
+
+        let x = ((u * self.width as f32) as usize).min(self.width.saturating_sub(1));
+        let y = ((v * self.height as f32) as usize).min(self.height.saturating_sub(1));
+        let pixel = self.pixel(x, y)?;
+
+        let alpha = pixel[3] as f32 / 255.0;
+        let back = background.as_array();
+        let channel = |index: usize| pixel[index] as f32 / 255.0 * alpha + back[index] * (1.0 - alpha);
+        Some(Color3D::from_array(&[channel(0), channel(1), channel(2), 1.0]))
+    }
+
+    pub fn aspect(&self) -> f32 {
+        if self.height == 0 { 0.0 } else { self.width as f32 / self.height as f32 }
+    }
+}
+
+/**
+ * Reads a sequence of PNG files, e.g. the frames of a video clip exported as images.
+ *
+ * Parameters:
+ *     paths: the path of each frame, in playback order
+ */
+pub fn load_png_sequence<P: AsRef<Path>>(paths: &[P]) -> Result<Vec<Image>, &'static str> {
+    paths.iter().map(|path| Image::from_png(path.as_ref())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::BufWriter;
+    use std::path::PathBuf;
+
+    /* 2x2 image: opaque red and green on top, opaque blue and half transparent white at the bottom */
+    fn image() -> Image {
+        Image::new(2, 2, vec![[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [255, 255, 255, 128]]).unwrap()
+    }
+
+    /* Writes a PNG file into the temporary directory and returns its path */
+    fn write_png(name: &str, width: u32, height: u32, color_type: png::ColorType, data: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("drone_light_show_{}_{}.png", std::process::id(), name));
+        let writer = BufWriter::new(File::create(&path).unwrap());
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(color_type);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header().unwrap().write_image_data(data).unwrap();
+        path
+    }
+
+    #[test]
+    fn new_checks_the_number_of_pixels() {
+        assert!(Image::new(2, 2, vec![[0, 0, 0, 255]; 3]).is_err());
+        assert!(Image::new(0, 0, Vec::new()).is_ok());
+        assert_eq!(image().aspect(), 1.0);
+        assert_eq!(Image::new(4, 2, vec![[0, 0, 0, 255]; 8]).unwrap().aspect(), 2.0);
+        assert_eq!(Image::new(0, 0, Vec::new()).unwrap().aspect(), 0.0);
+    }
+
+    #[test]
+    fn pixel_by_column_and_row() {
+        let image = image();
+
+        assert_eq!(image.pixel(1, 0), Some([0, 255, 0, 255]));
+        assert_eq!(image.pixel(0, 1), Some([0, 0, 255, 255]));
+        assert_eq!(image.pixel(2, 0), None);
+        assert_eq!(image.pixel(0, 2), None);
+    }
+
+    #[test]
+    fn sample_nearest_pixel() {
+        let image = image();
+        let black = Color3D::new(0, 0, 0);
+
+        assert_eq!(image.sample(0.0, 0.0, black), Some(Color3D::new(255, 0, 0)));
+        assert_eq!(image.sample(0.49, 0.2, black), Some(Color3D::new(255, 0, 0)));
+        assert_eq!(image.sample(0.5, 0.2, black), Some(Color3D::new(0, 255, 0)));
+        assert_eq!(image.sample(0.2, 0.75, black), Some(Color3D::new(0, 0, 255)));
+        //The right and bottom edges belong to the last column and row
+        assert_eq!(image.sample(1.0, 0.0, black), Some(Color3D::new(0, 255, 0)));
+    }
+
+    #[test]
+    fn sample_outside_the_image() {
+        let image = image();
+        let black = Color3D::new(0, 0, 0);
+
+        assert_eq!(image.sample(-0.1, 0.5, black), None);
+        assert_eq!(image.sample(0.5, 1.1, black), None);
+        assert_eq!(image.sample(f32::NAN, 0.5, black), None);
+        assert_eq!(Image::new(0, 0, Vec::new()).unwrap().sample(0.5, 0.5, black), None);
+    }
+
+    #[test]
+    fn sample_composites_over_the_background() {
+        let image = image();
+
+        assert_eq!(image.sample(0.75, 0.75, Color3D::new(0, 0, 0)), Some(Color3D::new(128, 128, 128)));
+        assert_eq!(image.sample(0.75, 0.75, Color3D::new(255, 0, 0)), Some(Color3D::new(255, 128, 128)));
+        assert_eq!(image.sample(0.25, 0.25, Color3D::new(0, 0, 255)), Some(Color3D::new(255, 0, 0)));
+    }
+
+    #[test]
+    fn load_rgba_png() {
+        let path = write_png("rgba", 2, 1, png::ColorType::Rgba, &[255, 0, 0, 255, 0, 0, 255, 0]);
+        let image = Image::from_png(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!((image.get_width(), image.get_height()), (2, 1));
+        assert_eq!(image.pixel(0, 0), Some([255, 0, 0, 255]));
+        assert_eq!(image.pixel(1, 0), Some([0, 0, 255, 0]));
+    }
+
+    #[test]
+    fn load_png_without_alpha_is_opaque() {
+        let path = write_png("rgb", 1, 2, png::ColorType::Rgb, &[10, 20, 30, 40, 50, 60]);
+        let image = Image::from_png(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!((image.get_width(), image.get_height()), (1, 2));
+        assert_eq!(image.pixel(0, 0), Some([10, 20, 30, 255]));
+        assert_eq!(image.pixel(0, 1), Some([40, 50, 60, 255]));
+    }
+
+    #[test]
+    fn load_grayscale_png() {
+        let path = write_png("gray", 2, 1, png::ColorType::Grayscale, &[0, 200]);
+        let gray = Image::from_png(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let path = write_png("gray_alpha", 1, 1, png::ColorType::GrayscaleAlpha, &[100, 50]);
+        let gray_alpha = Image::from_png(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(gray.pixel(1, 0), Some([200, 200, 200, 255]));
+        assert_eq!(gray_alpha.pixel(0, 0), Some([100, 100, 100, 50]));
+    }
+
+    #[test]
+    fn load_invalid_png() {
+        let path = std::env::temp_dir().join(format!("drone_light_show_{}_invalid.png", std::process::id()));
+        std::fs::write(&path, b"not a png").unwrap();
+        let invalid = Image::from_png(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(invalid.is_err());
+        assert!(Image::from_png(&path).is_err());
+    }
+
+    #[test]
+    fn load_sequence_in_order() {
+        let first = write_png("first", 1, 1, png::ColorType::Rgb, &[255, 0, 0]);
+        let second = write_png("second", 1, 1, png::ColorType::Rgb, &[0, 255, 0]);
+        let frames = load_png_sequence(&[&first, &second]);
+        let missing = load_png_sequence(&[first.clone(), first.with_extension("missing")]);
+        std::fs::remove_file(&first).unwrap();
+        std::fs::remove_file(&second).unwrap();
+
+        let frames = frames.unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].pixel(0, 0), Some([255, 0, 0, 255]));
+        assert_eq!(frames[1].pixel(0, 0), Some([0, 255, 0, 255]));
+        assert!(missing.is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use crate::colors::colors::ColorSpace;
+use crate::utils::frame_times;
+
+use super::light_program::LightProgram;
+
+/**
+ * LED calibration profile of a drone model, used to transform light programs at export
+ * time so the same show looks consistent on drones with different LED hardware.
+ */
+#[derive(Clone, Copy)]
+pub struct LedCalibration {
+    //Exponent applied to each channel after the gains; 1.0 keeps the response linear
+    pub gamma: f32,
+    //Gain of the red, green and blue channels, used to correct the white balance
+    pub gain: [f32; 3],
+    //Gain of the white channel, only used when has_white is set
+    pub white_gain: f32,
+    //Maximum brightness of every channel, in the range [0-1]
+    pub max_brightness: f32,
+    //Whether the LEDs have a white channel (RGBW) where the common part of RGB is moved
+    pub has_white: bool,
+}
+
+impl LedCalibration {
+    /** Returns the identity calibration of RGB hardware, which leaves colors unchanged */
+    pub fn new() -> Self {
+        Self {
+            gamma: 1.0,
+            gain: [1.0, 1.0, 1.0],
+            white_gain: 1.0,
+            max_brightness: 1.0,
+            has_white: false,
+        }
+    }
+
+    pub fn new_from(gamma: f32, gain: [f32; 3], white_gain: f32, max_brightness: f32, has_white: bool) -> Self {
+        Self { gamma, gain, white_gain, max_brightness, has_white }
+    }
+
+    pub fn as_dict(&self) -> HashMap<&'static str, f32> {
+        HashMap::from([
+            ("gamma", self.gamma),
+            ("gainR", self.gain[0]),
+            ("gainG", self.gain[1]),
+            ("gainB", self.gain[2]),
+            ("gainW", self.white_gain),
+            ("maxBrightness", self.max_brightness),
+            ("hasWhite", if self.has_white { 1.0 } else { 0.0 }),
+        ])
+    }
+
+    /**
+     * Transforms a color given as RGB channels in the range [0-1] into the values that
+     * must be sent to the LEDs.
+     *
+     * The white channel, if any, takes the minimum of the three channels, which is removed
+     * from them. Then the gains and the maximum brightness are applied and finally the gamma
+     * curve.
+     *
+     * Returns:
+     *     the red, green, blue and white channels in the range [0-1]; white is always 0 when
+     *     the hardware has no white channel
+     */
+    pub fn apply_array(&self, rgb: &[f32; 3]) -> [f32; 4] {
+        let rgb = [rgb[0].clamp(0.0, 1.0), rgb[1].clamp(0.0, 1.0), rgb[2].clamp(0.0, 1.0)];
+        let white = if self.has_white { rgb[0].min(rgb[1]).min(rgb[2]) } else { 0.0 };
+        let output = |value: f32, gain: f32| {
+            let scaled = (value * gain).clamp(0.0, 1.0) * self.max_brightness.clamp(0.0, 1.0);
+            if self.gamma > 0.0 { scaled.powf(self.gamma) } else { scaled }
+        };
+
+        [
+            output(rgb[0] - white, self.gain[0]),
+            output(rgb[1] - white, self.gain[1]),
+            output(rgb[2] - white, self.gain[2]),
+            output(white, self.white_gain)
+        ]
+    }
+
+    /**
+     * Transforms a light program into LED values. Timestamps and fade flags are kept, so
+     * fades are interpolated by the drone between calibrated keypoints.
+     *
+     * Drones only play linear fades in RGB, so eased fades are baked first with
+     * bake_easing(). When the calibration is not linear (gamma, white channel or saturating
+     * gains) or the program fades in another color space, fades are also sampled at the
+     * given rate, so the drone follows the calibrated colors of the program along the fade.
+     *
+     * Parameters:
+     *     program: the light program to export
+     *     fps: number of keypoints per second within eased or sampled fades
+     *
+     * Returns:
+     *     the calibrated keypoints, sorted by time
+     */
+    pub fn apply(&self, program: &LightProgram, fps: f32) -> Vec<LedColor4D> {
+        let program = program.bake_easing(fps);
+        let colors = program.get_colors();
+        let is_sampled = program.get_color_space() != ColorSpace::RGB || !self.is_linear();
+
+        //Keypoints as (time, Blender color array, is_fade)
+        let mut keypoints: Vec<(f32, [f32; 4], bool)> = Vec::new();
+        for (index, color) in colors.iter().enumerate() {
+            if is_sampled && color.is_fade() && index > 0 {
+                let prev = colors[index - 1].t;
+                let inner = frame_times(prev, color.t, fps).into_iter().filter(|t| *t > prev && *t < color.t);
+                keypoints.extend(inner.filter_map(|t| Some((t, program.array_at(t)?, true))));
+            }
+            keypoints.push((color.t, color.as_array(), color.is_fade()));
+        }
+
+        keypoints.into_iter()
+            .map(|(t, array, is_fade)| {
+                let output = self.apply_array(&[array[0], array[1], array[2]]);
+                let channel = |value: f32| (value * 255.0).round() as u8;
+                LedColor4D {
+                    t,
+                    r: channel(output[0]),
+                    g: channel(output[1]),
+                    b: channel(output[2]),
+                    w: channel(output[3]),
+                    is_fade
+                }
+            })
+            .collect()
+    }
+
+    /* Whether linear fades in RGB stay linear after the calibration */
+    fn is_linear(&self) -> bool {
+        (self.gamma == 1.0 || self.gamma <= 0.0)
+            && !self.has_white
+            && self.gain.iter().all(|gain| *gain <= 1.0)
+    }
+}
+
+/**
+ * Keypoint of a light program after LED calibration, ready to be exported to the drone.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LedColor4D {
+    pub t: f32,
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    //White channel, always 0 for RGB hardware
+    pub w: u8,
+    //Same meaning as in Color4D
+    is_fade: bool
+}
+
+impl LedColor4D {
+    pub fn is_fade(&self) -> bool {
+        self.is_fade
+    }
+
+    /** Returns the red, green, blue and white channels in the range [0-1] */
+    pub fn as_array(&self) -> [f32; 4] {
+        [self.r as f32 / 255.0, self.g as f32 / 255.0, self.b as f32 / 255.0, self.w as f32 / 255.0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::color::{Color3D, Color4D};
+    use crate::model::easing::Easing;
+
+    fn assert_array(actual: [f32; 4], expected: [f32; 4]) {
+        for i in 0..4 {
+            assert!((actual[i] - expected[i]).abs() < 1e-4, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    fn fade(from: [u8; 3], to: [u8; 3], duration: f32) -> Vec<Color4D> {
+        vec![
+            Color3D::new(from[0], from[1], from[2]).at_time(0.0, false),
+            Color3D::new(to[0], to[1], to[2]).at_time(duration, true),
+        ]
+    }
+
+    #[test]
+    fn identity_keeps_colors() {
+        let calibration = LedCalibration::new();
+        assert_array(calibration.apply_array(&[0.2, 0.4, 0.6]), [0.2, 0.4, 0.6, 0.0]);
+
+        let leds = calibration.apply(&LightProgram::new(fade([0, 0, 0], [255, 128, 0], 2.0)), 10.0);
+        assert_eq!(leds.len(), 2);
+        assert_eq!(leds[1].as_array(), [1.0, 128.0 / 255.0, 0.0, 0.0]);
+        assert!(leds[1].is_fade() && !leds[0].is_fade());
+    }
+
+    #[test]
+    fn gamma_curve() {
+        let calibration = LedCalibration::new_from(2.2, [1.0, 1.0, 1.0], 1.0, 1.0, false);
+        //0.5^2.2 and 0.25^2.2
+        assert_array(calibration.apply_array(&[0.5, 0.25, 1.0]), [0.217_637_6, 0.047_366_3, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn gains_and_max_brightness() {
+        //Gains correct the white point of the hardware, e.g. LEDs with a strong blue
+        let calibration = LedCalibration::new_from(1.0, [1.0, 0.9, 0.7], 1.0, 0.8, false);
+        assert_array(calibration.apply_array(&[1.0, 1.0, 1.0]), [0.8, 0.72, 0.56, 0.0]);
+
+        //Gains above 1 saturate
+        let calibration = LedCalibration::new_from(1.0, [2.0, 1.0, 1.0], 1.0, 1.0, false);
+        assert_array(calibration.apply_array(&[0.75, 0.5, 0.0]), [1.0, 0.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn white_channel() {
+        let calibration = LedCalibration::new_from(1.0, [1.0, 1.0, 1.0], 0.9, 1.0, true);
+        assert_array(calibration.apply_array(&[1.0, 0.8, 0.6]), [0.4, 0.2, 0.0, 0.54]);
+        assert_array(calibration.apply_array(&[1.0, 1.0, 1.0]), [0.0, 0.0, 0.0, 0.9]);
+    }
+
+    #[test]
+    fn gamma_fades_are_sampled() {
+        let calibration = LedCalibration::new_from(2.2, [1.0, 1.0, 1.0], 1.0, 1.0, false);
+        let program = LightProgram::new(fade([0, 0, 0], [255, 255, 255], 1.0));
+        let leds = calibration.apply(&program, 10.0);
+
+        assert_eq!(leds.len(), 11);
+        assert!(leds[1..].iter().all(|led| led.is_fade()));
+        //Halfway through the fade the program shows 0.5, which the LEDs show as 0.5^2.2
+        assert!((leds[5].t - 0.5).abs() < 1e-6);
+        assert_eq!(leds[5].r, (0.5_f32.powf(2.2) * 255.0).round() as u8);
+    }
+
+    #[test]
+    fn color_space_fades_are_sampled() {
+        let mut program = LightProgram::new(fade([255, 0, 0], [0, 255, 0], 1.0));
+        program.set_color_space(ColorSpace::HSV);
+        let leds = LedCalibration::new().apply(&program, 10.0);
+
+        //The HSV fade from red to green passes through yellow instead of brown
+        assert_eq!(leds.len(), 11);
+        assert_eq!((leds[5].r, leds[5].g, leds[5].b), (255, 255, 0));
+    }
+
+    #[test]
+    fn eased_fades_are_baked() {
+        let mut colors = fade([0, 0, 0], [200, 0, 0], 1.0);
+        colors[1] = colors[1].with_easing(Easing::STEPS(4));
+        let leds = LedCalibration::new().apply(&LightProgram::new(colors), 10.0);
+
+        assert_eq!(leds.iter().map(|led| led.r).collect::<Vec<u8>>(), vec![0, 50, 100, 150, 200]);
+        assert!(leds.iter().all(|led| !led.is_fade()));
+    }
+}
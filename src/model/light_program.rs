@@ -1,26 +1,78 @@
-use crate::utils::simplify_path;
+use crate::colors::colors::{perceptual_distance, ColorSpace};
+use crate::utils::{frame_times, simplify_path};
 
-use super::color::Color4D;
+use super::color::{Color3D, Color4D};
+use super::easing::Easing;
+
+//Maximum RGB channel difference used by LightProgram.simplify()
+const DEFAULT_SIMPLIFY_EPS: f32 = 4.0;
+
+//Perceptual distance used by LightProgram.simplify_perceptual(); about one just noticeable difference
+const PERCEPTUAL_EPS: f32 = 0.02;
+
+//Number of evaluations within each fade when measuring the error of a simplified program
+const FADE_SAMPLES: usize = 8;
+
+//Number of keypoints per second of the eased fades cut by LightProgram.slice()
+const SLICE_EASING_FPS: f32 = 50.0;
 
 /**
  * Simpliest representation of a causal light program in space and time.
  * 
  * The color between given points is linearly interpolated or kept constant
  * from past according to the is_fade property of each color4D element.
+ * Fades are interpolated in the color space of the program, RGB by default.
  */
-struct LightProgram {
-    colors: Vec<Color4D>
+#[derive(Clone)]
+pub struct LightProgram {
+    colors: Vec<Color4D>,
+    color_space: ColorSpace
 }
 
 impl LightProgram {
     pub fn new (colors: Vec<Color4D>) -> Self {
         let mut sorted_colors = colors;
-        sorted_colors.sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
+        sorted_colors.sort_by(|a, b| a.t.total_cmp(&b.t));
         Self {
-            colors: sorted_colors
+            colors: sorted_colors,
+            color_space: ColorSpace::RGB
         }
     }
 
+    /**
+     * Creates a light program by sampling a color function at a fixed frame rate.
+     * Consecutive samples are faded into each other.
+     *
+     * Parameters:
+     *     start: time of the first sample, in seconds
+     *     end: time after which no more samples are taken, in seconds; a single sample is
+     *          taken at start if end is not after it or the frame rate is not positive
+     *     fps: number of samples per second
+     *     color_at: the color at the given time, or None to skip the sample
+     */
+    pub fn from_function(start: f32, end: f32, fps: f32, color_at: impl Fn(f32) -> Option<Color3D>) -> Self {
+        let mut times = frame_times(start, end, fps);
+        if times.is_empty() { times.push(start) }
+
+        LightProgram::new(times.into_iter()
+            .filter_map(|t| color_at(t).map(|color| color.at_time(t, t > start)))
+            .collect())
+    }
+
+    pub fn get_color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /** Set the color space in which fades are interpolated */
+    pub fn set_color_space(&mut self, color_space: ColorSpace) {
+        self.color_space = color_space;
+    }
+
+    /** Return the colors of the light program, sorted by time */
+    pub fn get_colors(&self) -> &[Color4D] {
+        &self.colors
+    }
+
     /**
      * Add a color at the end of the light code
      */
@@ -32,6 +84,241 @@ impl LightProgram {
         }
     }
 
+    /** Return the timestamp of the first color of the program, if any */
+    pub fn start_time(&self) -> Option<f32> {
+        self.colors.first().map(|color| color.t)
+    }
+
+    /** Return the timestamp of the last color of the program, if any */
+    pub fn end_time(&self) -> Option<f32> {
+        self.colors.last().map(|color| color.t)
+    }
+
+    /**
+     * Returns a copy of the light program moved in time by the given number of seconds
+     */
+    pub fn shift(&self, delta: f32) -> LightProgram {
+        self.with_colors(self.colors.iter()
+            .map(|color| color.with_time(color.t + delta))
+            .collect())
+    }
+
+    /**
+     * Returns a copy of the light program with the time of every keypoint changed by the
+     * given function, e.g. to snap keypoints to beats or to follow a corrected tempo map.
+     */
+    pub fn retime(&self, map: impl Fn(f32) -> f32) -> LightProgram {
+        self.with_colors(self.colors.iter().map(|color| color.with_time(map(color.t))).collect())
+    }
+
+    /**
+     * Returns a copy of the light program stretched or compressed in time around a pivot.
+     *
+     * Parameters:
+     *     factor: the duration multiplier; values above 1 slow the program down
+     *     pivot: the time in seconds that stays in place
+     *
+     * Returns:
+     *     the scaled program, or an error if the factor is not positive or a parameter is
+     *     not finite
+     */
+    pub fn scale(&self, factor: f32, pivot: f32) -> Result<LightProgram, &'static str> {
+        if !factor.is_finite() || factor <= 0.0 { return Err("LightProgram.scale: The scale factor must be positive and finite") }
+        if !pivot.is_finite() { return Err("LightProgram.scale: The pivot must be finite") }
+
+        Ok(self.with_colors(self.colors.iter()
+            .map(|color| color.with_time(pivot + (color.t - pivot) * factor))
+            .collect()))
+    }
+
+    /**
+     * Returns the part of the light program between two times. The colors at both ends are
+     * evaluated, so the slice shows the same colors as the program within the window. Eased
+     * fades cut by the window can't keep their curve, so they are baked into linear fades as
+     * in bake_easing().
+     *
+     * Parameters:
+     *     start: start time of the window, in seconds
+     *     end: end time of the window, in seconds
+     */
+    pub fn slice(&self, start: f32, end: f32) -> Result<LightProgram, &'static str> {
+        if end < start { return Err("LightProgram.slice: The end of the window must not be before its start") }
+
+        let mut colors: Vec<Color4D> = Vec::new();
+        if let Some(color) = self.color_at(start) {
+            colors.push(color.at_time(start, false));
+        }
+        for (index, color) in self.colors.iter().enumerate() {
+            let is_cut = |t: f32| index > 0 && self.colors[index - 1].t < t && t < color.t;
+            if is_cut(start) || is_cut(end) {
+                let baked = self.bake_fade(&self.colors[index - 1], color, SLICE_EASING_FPS);
+                colors.extend(baked.into_iter().filter(|color| color.t > start && color.t <= end));
+            } else if color.t > start && color.t <= end {
+                colors.push(*color);
+            }
+        }
+
+        let has_end = colors.last().is_some_and(|last| last.t == end);
+        if let (false, Some(color)) = (has_end, self.color_at(end)) {
+            //Fading into the held color keeps it, so the end can always be a fade
+            colors.push(color.at_time(end, true));
+        }
+
+        Ok(self.with_colors(colors))
+    }
+
+    /**
+     * Returns a light program that plays this program and then the given one, moved in time
+     * so it starts when this one ends. The color space of this program is kept.
+     */
+    pub fn concat(&self, other: &LightProgram) -> LightProgram {
+        let (end, other_start) = match (self.end_time(), other.start_time()) {
+            (Some(end), Some(other_start)) => (end, other_start),
+            _ => return self.with_colors([self.colors.as_slice(), other.get_colors()].concat())
+        };
+
+        let mut colors = self.colors.clone();
+        colors.extend(other.shift(end - other_start).get_colors());
+
+        self.with_colors(colors)
+    }
+
+    /**
+     * Returns a light program that plays this program the given number of times in a row.
+     * An empty program is returned if count is zero.
+     */
+    pub fn repeat(&self, count: usize) -> LightProgram {
+        let mut program = self.with_colors(Vec::new());
+        for _ in 0..count {
+            program = program.concat(self);
+        }
+
+        program
+    }
+
+    /**
+     * Evaluates the light program at the given time as a Blender color array.
+     *
+     * If the next keypoint is a fade, the color is interpolated from the previous keypoint
+     * in the color space of the program, following the easing curve of the next keypoint;
+     * otherwise the previous color is held until the next keypoint. The first and last
+     * colors are held before and after the program.
+     *
+     * Parameters:
+     *     t: the time in seconds
+     *
+     * Returns:
+     *     the color at the given time, or None if the program has no colors
+     */
+    pub fn array_at(&self, t: f32) -> Option<[f32; 4]> {
+        let first = self.colors.first()?;
+        let last = self.colors.last()?;
+
+        if t < first.t { return Some(first.as_array()) }
+        if t >= last.t { return Some(last.as_array()) }
+
+        //Index of the first color strictly after t; it is always in [1, len - 1] here
+        let index = self.colors.partition_point(|color| color.t <= t);
+        let prev = self.colors[index - 1];
+        let next = self.colors[index];
+        let span = next.t - prev.t;
+        if !next.is_fade() || span <= 0.0 { return Some(prev.as_array()) }
+
+        let ratio = next.get_easing().apply((t - prev.t) / span);
+        Some(self.mix(&prev, &next, ratio))
+    }
+
+    /**
+     * Evaluates the light program at the given time, rounding the color to the nearest RGB
+     * value. See array_at() for the interpolation rules.
+     */
+    pub fn color_at(&self, t: f32) -> Option<Color3D> {
+        self.array_at(t).map(|color| Color3D::from_array(&color))
+    }
+
+    /**
+     * Samples the light program at a fixed frame rate.
+     *
+     * Parameters:
+     *     fps: the number of samples per second
+     *     start: the time of the first sample, in seconds
+     *     end: the time after which no more samples are taken, in seconds
+     *
+     * Returns:
+     *     one color per frame, placed at the time of the frame and fading from the previous one
+     */
+    pub fn sample(&self, fps: f32, start: f32, end: f32) -> Vec<Color4D> {
+        frame_times(start, end, fps)
+            .into_iter()
+            .filter_map(|t| self.color_at(t).map(|color| color.at_time(t, true)))
+            .collect()
+    }
+
+    /**
+     * Samples the light program at a fixed frame rate as Blender color arrays, without
+     * rounding. See sample() for the parameters.
+     */
+    pub fn sample_arrays(&self, fps: f32, start: f32, end: f32) -> Vec<[f32; 4]> {
+        frame_times(start, end, fps)
+            .into_iter()
+            .filter_map(|t| self.array_at(t))
+            .collect()
+    }
+
+    /**
+     * Replaces every eased fade with linear fades, for targets that only support linear
+     * interpolation. Step curves become exact abrupt changes; other curves are approximated
+     * with linear fades between keypoints placed at the given rate.
+     *
+     * The color space of the program is kept, so the fades of HSV, HSL or OKLab programs are
+     * still not linear in RGB.
+     *
+     * Parameters:
+     *     fps: number of keypoints per second within eased fades
+     *
+     * Return:
+     *     LightProgram instance where every fade is linear
+     */
+    pub fn bake_easing(&self, fps: f32) -> LightProgram {
+        let mut colors: Vec<Color4D> = Vec::new();
+
+        for (index, color) in self.colors.iter().enumerate() {
+            match index.checked_sub(1) {
+                Some(prev) => colors.extend(self.bake_fade(&self.colors[prev], color, fps)),
+                None => colors.push(color.with_easing(Easing::LINEAR))
+            }
+        }
+
+        self.with_colors(colors)
+    }
+
+    /*
+     * Keypoints with linear fades that replace the change from one keypoint to the next one,
+     * the next one included. See bake_easing().
+     */
+    fn bake_fade(&self, prev: &Color4D, color: &Color4D, fps: f32) -> Vec<Color4D> {
+        let span = color.t - prev.t;
+        let (steps, is_fade) = match color.get_easing() {
+            Easing::LINEAR | Easing::STEPS(0) => (1, true),
+            Easing::STEPS(steps) => (steps as usize, false),
+            _ => (((span * fps).ceil() as usize).max(1), true)
+        };
+        if !color.is_fade() || span <= 0.0 || steps == 1 && is_fade {
+            return vec![color.with_easing(Easing::LINEAR)]
+        }
+
+        let mut colors: Vec<Color4D> = (1..steps)
+            .map(|step| {
+                let ratio = step as f32 / steps as f32;
+                let mixed = self.mix(prev, color, color.get_easing().apply(ratio));
+                Color3D::from_array(&mixed).at_time(prev.t + ratio * span, is_fade)
+            })
+            .collect();
+        colors.push(color.as_3d().at_time(color.t, is_fade));
+
+        colors
+    }
+
     /**
      * Simplifies the light code by removing unnecesary keypoints from it.
      * 
@@ -39,43 +326,497 @@ impl LightProgram {
      *     LightProgram instance with the simplified light code
      */
     pub fn simplify(&self) -> LightProgram {
-        let new_items = simplify_path(self.colors.clone(), 4.0, simplify_color_distante_func);
-        LightProgram::new(new_items)
+        self.simplify_with(DEFAULT_SIMPLIFY_EPS, f32::INFINITY).0
+    }
+
+    /**
+     * Simplifies the light code by removing keypoints that can be faded from their
+     * neighbours. Only keypoints inside linear fades are removed, so abrupt changes of color
+     * and eased fades are always kept. Fades are compared in the color space of the program.
+     *
+     * Parameters:
+     *     tolerance: maximum difference allowed in any RGB channel, in the range [0-255]
+     *     max_gap: maximum time between consecutive keypoints, in seconds; keypoints are
+     *              kept whenever removing them would leave a longer gap
+     *
+     * Returns:
+     *     LightProgram instance with the simplified light code and a report of the changes
+     */
+    pub fn simplify_with(&self, tolerance: f32, max_gap: f32) -> (LightProgram, SimplifyReport) {
+        let space = self.color_space;
+        let program = self.simplify_fades(tolerance, max_gap, |keypoints, start, end| {
+            fade_errors(keypoints, start, end, space, channel_difference)
+        });
+        let report = SimplifyReport {
+            removed: self.colors.len() - program.colors.len(),
+            max_error: self.max_difference(&program)
+        };
+
+        (program, report)
+    }
+
+    /**
+     * Simplifies the light code by removing keypoints whose color can be interpolated from
+     * their neighbours, in the color space of the program, without a noticeable difference.
+     * The difference is measured as a perceptual distance in OKLab. As in simplify_with(),
+     * abrupt changes of color and eased fades are always kept.
+     * 
+     * Return:
+     *     LightProgram instance with the simplified light code
+     */
+    pub fn simplify_perceptual(&self) -> LightProgram {
+        let space = self.color_space;
+        self.simplify_fades(PERCEPTUAL_EPS, f32::INFINITY, |keypoints, start, end| {
+            fade_errors(keypoints, start, end, space, perceptual_distance)
+        })
+    }
+
+    /*
+     * Simplifies every chain of linear fades on its own. See simplify_with() for the
+     * parameters; distance_func measures the error of each keypoint of a chain, as expected
+     * by simplify_path().
+     */
+    fn simplify_fades(&self, tolerance: f32, max_gap: f32, distance_func: impl Fn(&Vec<Color4D>, &Color4D, &Color4D) -> Vec<f32>) -> LightProgram {
+        let mut colors: Vec<Color4D> = Vec::new();
+
+        for chain in fade_chains(&self.colors) {
+            let kept = limit_gaps(chain, &simplify_path(chain.to_vec(), tolerance, &distance_func), max_gap);
+            colors.push(chain[kept[0]]);
+
+            //Keypoints added back by limit_gaps() split a simplified fade, so each part is simplified again
+            for pair in kept.windows(2) {
+                let part = simplify_path(chain[pair[0]..=pair[1]].to_vec(), tolerance, &distance_func);
+                colors.extend(&part[1..]);
+            }
+        }
+
+        self.with_colors(colors)
     }
+
+    /*
+     * Largest difference in any RGB channel between this program and another one, in the
+     * range [0-255], evaluated at every keypoint of this program and within its fades
+     */
+    fn max_difference(&self, other: &LightProgram) -> f32 {
+        let mut times: Vec<f32> = Vec::new();
+        for (index, color) in self.colors.iter().enumerate() {
+            if let Some(previous) = index.checked_sub(1).map(|previous| self.colors[previous].t) {
+                times.extend((1..FADE_SAMPLES).map(|step| previous + step as f32 / FADE_SAMPLES as f32 * (color.t - previous)));
+            }
+            times.push(color.t);
+        }
+
+        times.iter()
+            .filter_map(|t| Some(channel_difference(&rgb(&self.array_at(*t)?), &rgb(&other.array_at(*t)?))))
+            .fold(0.0, f32::max)
+    }
+
+    /* Color at the given fraction of the change between two keypoints */
+    fn mix(&self, prev: &Color4D, next: &Color4D, ratio: f32) -> [f32; 4] {
+        let (from, to) = (prev.as_array(), next.as_array());
+        let mixed = self.color_space.interpolate(&[from[0], from[1], from[2]], &[to[0], to[1], to[2]], ratio);
+        [mixed[0], mixed[1], mixed[2], 1.0]
+    }
+
+    /* New light program with the given colors and the color space of this one */
+    fn with_colors(&self, colors: Vec<Color4D>) -> LightProgram {
+        let mut program = LightProgram::new(colors);
+        program.set_color_space(self.color_space);
+
+        program
+    }
+}
+
+/**
+ * Summary of the changes made by LightProgram.simplify_with()
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SimplifyReport {
+    //Number of keypoints removed
+    pub removed: usize,
+    //Largest difference in any RGB channel between the original and simplified programs, in the range [0-255]
+    pub max_error: f32,
+}
+
+/*
+ * Splits a list of colors into chains that start at an abrupt or eased keypoint and are
+ * followed only by linear fades
+ */
+fn fade_chains(colors: &[Color4D]) -> Vec<&[Color4D]> {
+    let mut chains: Vec<&[Color4D]> = Vec::new();
+    let mut start = 0;
+
+    for (index, color) in colors.iter().enumerate().skip(1) {
+        if !color.is_fade() || !color.get_easing().is_linear() {
+            chains.push(&colors[start..index]);
+            start = index;
+        }
+    }
+    if start < colors.len() { chains.push(&colors[start..]) }
+
+    chains
+}
+
+/*
+ * Adds back to the kept keypoints of a chain the removed ones needed so that no gap between
+ * consecutive keypoints is longer than max_gap, when the original chain allows it
+ *
+ * Returns:
+ *     the indices in the chain of the keypoints to keep
+ */
+fn limit_gaps(chain: &[Color4D], kept: &[Color4D], max_gap: f32) -> Vec<usize> {
+    let mut result: Vec<usize> = Vec::new();
+    let mut next_kept = 0;
+
+    for (index, color) in chain.iter().enumerate() {
+        if kept.get(next_kept) == Some(color) {
+            next_kept += 1;
+            result.push(index);
+            continue;
+        }
+
+        let is_needed = match (result.last(), chain.get(index + 1)) {
+            (Some(last), Some(next)) => next.t - chain[*last].t > max_gap,
+            _ => false
+        };
+        if is_needed { result.push(index) }
+    }
+
+    result
 }
 
-/*Distance function for LightProgram.simplify() */
-fn simplify_color_distante_func(keypoints: &Vec<Color4D>, start: &Color4D, end: &Color4D) -> Vec<f32> {
+/*
+ * Error of each keypoint of a chain of linear fades if the chain was replaced by a single
+ * fade from start to end, interpolated in the given color space: the largest error within
+ * the fades into and out of the keypoint
+ */
+fn fade_errors(keypoints: &[Color4D], start: &Color4D, end: &Color4D, space: ColorSpace, error: fn(&[f32; 3], &[f32; 3]) -> f32) -> Vec<f32> {
     let timespan = end.t - start.t;
-    let timespan2: f32;
-    if timespan > 0.0  {timespan2 = timespan} else {timespan2 = 0.5}
+    let simplified = |t: f32| {
+        let ratio = if timespan > 0.0 { (t - start.t) / timespan } else { 0.0 };
+        space.interpolate(&rgb(&start.as_array()), &rgb(&end.as_array()), ratio)
+    };
 
-    let mut result: Vec<f32> = vec![];
+    //Largest error within the fade into each keypoint, the keypoint included
+    let fade_into: Vec<f32> = keypoints.iter()
+        .enumerate()
+        .map(|(index, keypoint)| {
+            let previous = if index > 0 { &keypoints[index - 1] } else { keypoint };
+            (1..=FADE_SAMPLES)
+                .map(|step| {
+                    let ratio = step as f32 / FADE_SAMPLES as f32;
+                    let original = space.interpolate(&rgb(&previous.as_array()), &rgb(&keypoint.as_array()), ratio);
+                    error(&original, &simplified(previous.t + ratio * (keypoint.t - previous.t)))
+                })
+                .fold(0.0, f32::max)
+        })
+        .collect();
 
-    for point in keypoints {
-        let ratio = (point.t - start.t) / timespan2;
+    (0..keypoints.len())
+        .map(|index| fade_into[index].max(fade_into.get(index + 1).copied().unwrap_or(0.0)))
+        .collect()
+}
 
-        let interp: [f32; 3] = [
-            start.r as f32 + ratio * (end.r - start.r) as f32,
-            start.g as f32 + ratio * (end.g - start.g) as f32,
-            start.b as f32 + ratio * (end.b - start.b) as f32
-        ];
+/* Largest difference in any channel between two RGB colors, in the range [0-255] */
+fn channel_difference(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    (0..3).map(|channel| (a[channel] - b[channel]).abs() * 255.0).fold(0.0, f32::max)
+}
+
+/* RGB channels of a Blender color array */
+fn rgb(color: &[f32; 4]) -> [f32; 3] {
+    [color[0], color[1], color[2]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color(t: f32, rgb: [u8; 3], is_fade: bool) -> Color4D {
+        Color3D::new(rgb[0], rgb[1], rgb[2]).at_time(t, is_fade)
+    }
+
+    fn program(colors: Vec<Color4D>, space: ColorSpace) -> LightProgram {
+        let mut program = LightProgram::new(colors);
+        program.set_color_space(space);
+        program
+    }
+
+    /* Largest difference in any RGB channel between two programs, sampled densely */
+    fn sampled_error(a: &LightProgram, b: &LightProgram) -> f32 {
+        let end = a.end_time().unwrap();
+        frame_times(a.start_time().unwrap(), end, 500.0)
+            .into_iter()
+            .chain([end])
+            .map(|t| channel_difference(&rgb(&a.array_at(t).unwrap()), &rgb(&b.array_at(t).unwrap())))
+            .fold(0.0, f32::max)
+    }
+
+    /* Slow hue rotation with small wobbles in value, sampled at 10 keypoints per second */
+    fn rainbow(space: ColorSpace) -> LightProgram {
+        let colors = (0..=80)
+            .map(|index| {
+                let t = index as f32 / 10.0;
+                let value = 0.8 + 0.02 * (t * 7.0).sin();
+                let rgb = Color3D::from_hsv(&[t * 40.0, 0.9, value]);
+                rgb.at_time(t, index > 0)
+            })
+            .collect();
+        program(colors, space)
+    }
+
+    /* Red held until a cut to green at 1 s, then a linear fade to blue at 3 s and an eased fade to white at 5 s */
+    fn cues() -> LightProgram {
+        LightProgram::new(vec![
+            color(0.0, [255, 0, 0], false),
+            color(1.0, [0, 255, 0], false),
+            color(3.0, [0, 0, 255], true),
+            color(5.0, [255, 255, 255], true).with_easing(Easing::EASE_IN),
+        ])
+    }
+
+    #[test]
+    fn evaluate_empty_program() {
+        let empty = LightProgram::new(Vec::new());
+
+        assert_eq!(empty.array_at(0.0), None);
+        assert_eq!(empty.color_at(0.0), None);
+        assert!(empty.sample(10.0, 0.0, 1.0).is_empty());
+    }
+
+    #[test]
+    fn evaluate_outside_program() {
+        let program = cues();
+
+        assert_eq!(program.color_at(-1.0), Some(Color3D::new(255, 0, 0)));
+        assert_eq!(program.color_at(5.0), Some(Color3D::new(255, 255, 255)));
+        assert_eq!(program.color_at(100.0), Some(Color3D::new(255, 255, 255)));
+    }
+
+    #[test]
+    fn evaluate_held_color() {
+        let program = cues();
+
+        assert_eq!(program.color_at(0.0), Some(Color3D::new(255, 0, 0)));
+        assert_eq!(program.color_at(0.999), Some(Color3D::new(255, 0, 0)));
+        //The new color is shown exactly at its keypoint
+        assert_eq!(program.color_at(1.0), Some(Color3D::new(0, 255, 0)));
+    }
+
+    #[test]
+    fn evaluate_fades() {
+        let program = cues();
+
+        assert_eq!(program.array_at(2.0), Some([0.0, 0.5, 0.5, 1.0]));
+        assert_eq!(program.color_at(3.0), Some(Color3D::new(0, 0, 255)));
 
-        let diff = [
-            (interp[0] - point.r as f32).abs(),
-            (interp[1] - point.g as f32).abs(),
-            (interp[2] - point.b as f32).abs()
+        let eased = Easing::EASE_IN.apply(0.5);
+        let [r, g, b, _] = program.array_at(4.0).unwrap();
+        assert!(eased < 0.4);
+        assert!((r - eased).abs() < 1e-6 && (g - eased).abs() < 1e-6 && b == 1.0, "{} {} {}", r, g, b);
+    }
+
+    #[test]
+    fn sample_at_frame_rate() {
+        let program = cues();
+        let samples = program.sample(2.0, 0.5, 3.2);
+
+        assert_eq!(samples.iter().map(|sample| sample.t).collect::<Vec<_>>(), vec![0.5, 1.0, 1.5, 2.0, 2.5, 3.0]);
+        assert!(samples.iter().all(|sample| sample.is_fade()));
+        assert_eq!(samples[3].as_3d(), Color3D::new(0, 128, 128));
+
+        let arrays = program.sample_arrays(2.0, 0.5, 3.2);
+        assert_eq!(arrays.len(), samples.len());
+        assert_eq!(arrays[3], [0.0, 0.5, 0.5, 1.0]);
+        assert_eq!(arrays[0], [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    /* Times, colors and fade flags of the keypoints of a program */
+    fn keypoints(program: &LightProgram) -> Vec<(f32, [u8; 3], bool)> {
+        program.get_colors().iter().map(|color| (color.t, [color.r, color.g, color.b], color.is_fade())).collect()
+    }
+
+    /* Black at 1 s fading to white at 3 s */
+    fn fade_in() -> LightProgram {
+        program(vec![color(1.0, [0, 0, 0], false), color(3.0, [255, 255, 255], true)], ColorSpace::HSV)
+    }
+
+    #[test]
+    fn shift_moves_every_keypoint() {
+        let shifted = fade_in().shift(-0.5);
+
+        assert_eq!(keypoints(&shifted), vec![(0.5, [0, 0, 0], false), (2.5, [255, 255, 255], true)]);
+        assert_eq!(shifted.get_color_space(), ColorSpace::HSV);
+    }
+
+    #[test]
+    fn scale_times_and_fades_around_pivot() {
+        let slower = fade_in().scale(2.0, 1.0).unwrap();
+        assert_eq!(keypoints(&slower), vec![(1.0, [0, 0, 0], false), (5.0, [255, 255, 255], true)]);
+        assert_eq!(slower.color_at(3.0), Some(Color3D::new(128, 128, 128)));
+        assert_eq!(slower.get_color_space(), ColorSpace::HSV);
+
+        let faster = fade_in().scale(0.5, 3.0).unwrap();
+        assert_eq!(keypoints(&faster), vec![(2.0, [0, 0, 0], false), (3.0, [255, 255, 255], true)]);
+    }
+
+    #[test]
+    fn scale_rejects_invalid_factors() {
+        for factor in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(fade_in().scale(factor, 0.0).is_err(), "{}", factor);
+        }
+        assert!(fade_in().scale(2.0, f32::NAN).is_err());
+    }
+
+    #[test]
+    fn concat_starts_the_second_program_at_the_end_of_the_first() {
+        let red = LightProgram::new(vec![color(5.0, [255, 0, 0], false), color(6.0, [0, 0, 0], true)]);
+        let program = fade_in().concat(&red);
+
+        assert_eq!(keypoints(&program), vec![
+            (1.0, [0, 0, 0], false), (3.0, [255, 255, 255], true), (3.0, [255, 0, 0], false), (4.0, [0, 0, 0], true),
+        ]);
+        assert_eq!(program.get_color_space(), ColorSpace::HSV);
+        //The second program is shown from its first keypoint on
+        assert_eq!(program.color_at(2.99).map(|color| color.get_r() > 250), Some(true));
+        assert_eq!(program.color_at(3.0), Some(Color3D::new(255, 0, 0)));
+
+        let empty = LightProgram::new(Vec::new());
+        assert_eq!(keypoints(&empty.concat(&red)), keypoints(&red));
+        assert_eq!(keypoints(&red.concat(&empty)), keypoints(&red));
+    }
+
+    #[test]
+    fn repeat_counts() {
+        let program = fade_in();
+
+        assert!(program.repeat(0).get_colors().is_empty());
+        assert_eq!(program.repeat(0).get_color_space(), ColorSpace::HSV);
+        assert_eq!(keypoints(&program.repeat(1)), keypoints(&program));
+
+        let repeated = program.repeat(3);
+        assert_eq!(repeated.get_colors().len(), 6);
+        assert_eq!(repeated.start_time(), Some(1.0));
+        assert_eq!(repeated.end_time(), Some(7.0));
+        assert_eq!(repeated.color_at(4.0), Some(Color3D::new(128, 128, 128)));
+        assert_eq!(repeated.color_at(5.0), Some(Color3D::new(0, 0, 0)));
+    }
+
+    #[test]
+    fn slice_keeps_eased_fades() {
+        let original = LightProgram::new(vec![
+            color(0.0, [0, 0, 0], false),
+            color(2.0, [255, 255, 255], true).with_easing(Easing::EASE_IN),
+            color(4.0, [0, 0, 255], true).with_easing(Easing::EASE_IN),
+        ]);
+
+        for (start, end) in [(0.5, 1.5), (1.0, 3.0), (0.0, 2.0), (2.5, 4.0)] {
+            let slice = original.slice(start, end).unwrap();
+            assert_eq!(slice.start_time(), Some(start));
+            assert_eq!(slice.end_time(), Some(end));
+
+            let max_error = frame_times(start, end, 500.0)
+                .into_iter()
+                .chain([end])
+                .map(|t| channel_difference(&rgb(&original.array_at(t).unwrap()), &rgb(&slice.array_at(t).unwrap())))
+                .fold(0.0, f32::max);
+            assert!(max_error <= 1.0, "{} {} {}", start, end, max_error);
+        }
+    }
+
+    #[test]
+    fn slice_keeps_steps_exact() {
+        let original = LightProgram::new(vec![color(0.0, [0, 0, 0], false), color(4.0, [200, 0, 0], true).with_easing(Easing::STEPS(4))]);
+        let slice = original.slice(0.5, 2.5).unwrap();
+
+        for t in [0.5, 0.99, 1.0, 1.5, 2.0, 2.5] {
+            assert_eq!(slice.color_at(t), original.color_at(t), "{}", t);
+        }
+    }
+
+    #[test]
+    fn simplify_honors_tolerance_in_every_color_space() {
+        for space in [ColorSpace::RGB, ColorSpace::HSV, ColorSpace::HSL, ColorSpace::OKLAB] {
+            for tolerance in [2.0, 4.0, 16.0] {
+                let original = rainbow(space);
+                let (simplified, report) = original.simplify_with(tolerance, f32::INFINITY);
+
+                assert!(report.removed > 0, "{:?}", space);
+                assert_eq!(report.removed, original.get_colors().len() - simplified.get_colors().len());
+                assert!(report.max_error <= tolerance, "{:?} {} {}", space, tolerance, report.max_error);
+                assert!(sampled_error(&original, &simplified) <= tolerance, "{:?} {}", space, tolerance);
+            }
+        }
+    }
+
+    #[test]
+    fn simplify_interpolates_in_the_color_space_of_the_program() {
+        //The midpoint is close to the RGB fade from red to green, but far from the HSV one
+        let colors = vec![color(0.0, [255, 0, 0], false), color(1.0, [128, 128, 0], true), color(2.0, [0, 255, 0], true)];
+
+        let (simplified, _) = program(colors.clone(), ColorSpace::RGB).simplify_with(4.0, f32::INFINITY);
+        assert_eq!(simplified.get_colors().len(), 2);
+
+        let (simplified, report) = program(colors, ColorSpace::HSV).simplify_with(4.0, f32::INFINITY);
+        assert_eq!(simplified.get_colors().len(), 3);
+        assert_eq!(simplified.color_at(1.0), Some(Color3D::new(128, 128, 0)));
+        assert_eq!(report.max_error, 0.0);
+    }
+
+    #[test]
+    fn simplify_removes_redundant_keypoints() {
+        let colors = vec![color(0.0, [0, 0, 0], false), color(1.0, [50, 50, 50], true), color(2.0, [100, 100, 100], true)];
+        let (simplified, report) = program(colors, ColorSpace::RGB).simplify_with(4.0, f32::INFINITY);
+
+        assert_eq!(simplified.get_colors().len(), 2);
+        assert_eq!(report.removed, 1);
+        assert!(report.max_error <= 0.5);
+    }
+
+    #[test]
+    fn simplify_keeps_hard_cuts() {
+        let colors = vec![
+            color(0.0, [0, 0, 0], false),
+            color(1.0, [50, 50, 50], true),
+            color(2.0, [100, 100, 100], true),
+            color(2.0, [255, 0, 0], false),
+            color(3.0, [255, 0, 0], false),
+            color(4.0, [0, 0, 255], false),
+            color(5.0, [0, 0, 255], true),
         ];
 
-        let mut max: f32 = diff[0];
-        for number in 1..3 {
-            if diff[number] > max {
-                max = diff[number];
+        for space in [ColorSpace::RGB, ColorSpace::HSV, ColorSpace::HSL, ColorSpace::OKLAB] {
+            let original = program(colors.clone(), space);
+            for simplified in [original.simplify_with(4.0, f32::INFINITY).0, original.simplify_perceptual()] {
+                let kept = simplified.get_colors();
+                assert!(colors.iter().filter(|color| !color.is_fade()).all(|color| kept.contains(color)), "{:?}", space);
+                assert_eq!(simplified.color_at(3.99), Some(Color3D::new(255, 0, 0)));
+                assert!(sampled_error(&original, &simplified) < 1e-3, "{:?}", space);
             }
         }
+    }
 
-        result.push(max);
+    #[test]
+    fn simplify_keeps_eased_fades() {
+        let colors = vec![
+            color(0.0, [0, 0, 0], false),
+            color(1.0, [100, 100, 100], true).with_easing(Easing::EASE_IN),
+            color(2.0, [200, 200, 200], true),
+        ];
+        let original = program(colors, ColorSpace::RGB);
+
+        let (simplified, report) = original.simplify_with(4.0, f32::INFINITY);
+        assert_eq!(simplified.get_colors().len(), 3);
+        assert_eq!(report.removed, 0);
+        assert_eq!(original.simplify_perceptual().get_colors().len(), 3);
     }
 
-    result
-}
\ No newline at end of file
+    #[test]
+    fn simplify_limits_gaps_without_exceeding_tolerance() {
+        let original = rainbow(ColorSpace::HSV);
+        let (simplified, report) = original.simplify_with(8.0, 0.5);
+
+        assert!(simplified.get_colors().windows(2).all(|pair| pair[1].t - pair[0].t <= 0.5 + 1e-4));
+        assert!(report.max_error <= 8.0);
+        assert!(sampled_error(&original, &simplified) <= 8.0);
+    }
+}
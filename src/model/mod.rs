@@ -1,9 +1,13 @@
 pub mod cameras;
 pub mod color;
+pub mod easing;
+pub mod image;
+pub mod led_calibration;
 pub mod light_program;
 pub mod plane;
 pub mod point;
 pub mod safety_check;
+pub mod tempo_map;
 pub mod time_markers;
 pub mod trajectory;
 pub mod yaw;
\ No newline at end of file
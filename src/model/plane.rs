@@ -10,7 +10,8 @@ use std::fmt::Error;
 
  pub type Coordinate3D = [f32; 3];
 
-struct Plane {
+#[derive(Clone, Copy)]
+pub struct Plane {
     //The normal vector of the plane
     normal: Coordinate3D,
     //The offset parameter of the plane equation
@@ -32,7 +33,7 @@ impl Plane {
         let pr = [r[0] - p[0], r[1] - p[1], r[2] - p[2]];
         let normal = [
             pq[1] * pr[2] - pq[2] * pr[1],
-            pq[0] * pr[2] - pq[2] * pr[0],
+            pq[2] * pr[0] - pq[0] * pr[2],
             pq[0] * pr[1] - pq[1] * pr[0]
         ];
 
@@ -40,7 +41,7 @@ impl Plane {
             return Err("The given points are collinear")
         }
 
-        Ok(Self { normal, offset: 0.0 })
+        Self::form_normal_and_point(normal, p)
     } 
 
     /**
@@ -49,14 +50,24 @@ impl Plane {
      * Args:
      *    normal: the normal vector.
      *    point: the point on the plane
+     *
+     * Returns:
+     *    the plane, or an error if the normal vector is zero or a coordinate is not finite
      */
-    pub fn form_normal_and_point(normal: Coordinate3D, point: Coordinate3D) -> Self {
+    pub fn form_normal_and_point(normal: Coordinate3D, point: Coordinate3D) -> Result<Self, &'static str> {
+        if normal.iter().chain(point.iter()).any(|value| !value.is_finite()) {
+            return Err("The normal and the point must be finite")
+        }
+        if normal[0] == 0.0 && normal[1] == 0.0 && normal[2] == 0.0 {
+            return Err("The normal vector must not be zero")
+        }
+
         let offset = point[0] * normal[0] + point[1] * normal[1] + point[2] * normal[2];
-        
-        Self {
+
+        Ok(Self {
             normal,
             offset
-        }
+        })
     }
 
     /**
@@ -68,4 +79,57 @@ impl Plane {
 
         x >= self.offset
     }
-}
\ No newline at end of file
+
+    /**
+     * Returns the signed distance of the given point from the plane. The distance is
+     * positive on the front side of the plane.
+     */
+    pub fn distance(&self, point: Coordinate3D) -> f32 {
+        let x = self.normal[0] * point[0] + self.normal[1] * point[1] + self.normal[2] * point[2];
+        let norm = (self.normal[0].powi(2) + self.normal[1].powi(2) + self.normal[2].powi(2)).sqrt();
+
+        (x - self.offset) / norm
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-5, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn plane_from_points() {
+        //The plane z = 2, with its front side up since the points are counterclockwise seen from above
+        let plane = Plane::from_points([0.0, 0.0, 2.0], [1.0, 0.0, 2.0], [0.0, 1.0, 2.0]).unwrap();
+
+        assert_close(plane.distance([5.0, -3.0, 2.0]), 0.0);
+        assert_close(plane.distance([0.0, 0.0, 5.0]), 3.0);
+        assert_close(plane.distance([1.0, 1.0, 0.0]), -2.0);
+        assert!(plane.is_front([0.0, 0.0, 3.0]));
+        assert!(!plane.is_front([0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn plane_from_tilted_points() {
+        //The plane x + y = 2, which needs every term of the cross product
+        let plane = Plane::from_points([2.0, 0.0, 0.0], [0.0, 2.0, 0.0], [2.0, 0.0, 1.0]).unwrap();
+
+        assert_close(plane.distance([1.0, 1.0, 7.0]), 0.0);
+        assert_close(plane.distance([0.0, 0.0, 0.0]).abs(), 2.0f32.sqrt());
+        assert_close(plane.distance([2.0, 2.0, 0.0]), -plane.distance([0.0, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn plane_from_normal_and_point() {
+        let plane = Plane::form_normal_and_point([0.0, 0.0, 4.0], [0.0, 0.0, 1.0]).unwrap();
+
+        assert_close(plane.distance([3.0, 3.0, 3.0]), 2.0);
+        assert!(Plane::form_normal_and_point([0.0, 0.0, 0.0], [0.0, 0.0, 1.0]).is_err());
+        assert!(Plane::form_normal_and_point([0.0, f32::NAN, 1.0], [0.0, 0.0, 1.0]).is_err());
+        assert!(Plane::form_normal_and_point([0.0, 0.0, 1.0], [f32::INFINITY, 0.0, 1.0]).is_err());
+        assert!(Plane::from_points([0.0, 0.0, 0.0], [1.0, 1.0, 1.0], [2.0, 2.0, 2.0]).is_err());
+    }
+}
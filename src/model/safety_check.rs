@@ -5,13 +5,17 @@ use super::plane::Coordinate3D;
 /**
  * Safety Check parameters
  */
-struct SafetyCheckParams {
-    max_altitude: f32,
-    max_velocity_xy: f32,
-    max_velocity_z: f32,
-    min_distance: f32,
-    max_velocity_z_up: f32,
-    min_nav_altitude: f32,
+#[derive(Clone, Copy)]
+pub struct SafetyCheckParams {
+    pub max_altitude: f32,
+    pub max_velocity_xy: f32,
+    pub max_velocity_z: f32,
+    pub min_distance: f32,
+    //Maximum upwards vertical velocity; zero or less means that max_velocity_z applies
+    pub max_velocity_z_up: f32,
+    pub min_nav_altitude: f32,
+    //Maximum horizontal distance from the takeoff or landing position while below min_nav_altitude
+    pub corridor_radius: f32,
 }
 
 impl SafetyCheckParams {
@@ -23,6 +27,7 @@ impl SafetyCheckParams {
             min_distance: 3.0,
             max_velocity_z_up: 0.0,
             min_nav_altitude: 2.5,
+            corridor_radius: 1.5,
         }
     }
 
@@ -33,6 +38,7 @@ impl SafetyCheckParams {
         min_distance: f32,
         max_velocity_z_up: f32,
         min_nav_altitude: f32,
+        corridor_radius: f32,
     ) -> Self {
         Self {
             max_altitude,
@@ -41,6 +47,7 @@ impl SafetyCheckParams {
             min_distance,
             max_velocity_z_up,
             min_nav_altitude,
+            corridor_radius,
         }
     }
 
@@ -52,6 +59,7 @@ impl SafetyCheckParams {
             ("minDistance", self.min_distance),
             ("minNavAltitude", self.min_nav_altitude),
             ("maxVelocityZUp", self.max_velocity_z_up),
+            ("corridorRadius", self.corridor_radius),
         ])
     }
 }
@@ -59,7 +67,8 @@ impl SafetyCheckParams {
 /**
  * Instance of this struct hold the result of a single safety check
  */
-struct SafetyCheckResult {
+#[derive(Clone, PartialEq, Debug)]
+pub struct SafetyCheckResult {
     pub drones_over_max_altitude: Vec<Coordinate3D>,
     pub drones_over_max_velocity_xy: Vec<Coordinate3D>,
     pub drones_over_max_velocity_z: Vec<Coordinate3D>,
@@ -68,6 +77,10 @@ struct SafetyCheckResult {
     pub min_distance: Vec<f32>,
     pub min_altitude: Vec<f32>,
     pub all_close_pairs: Vec<(Coordinate3D, Coordinate3D)>,
+    //Positions of the drones that leave the vertical takeoff/landing corridor or touch the ground mid-show
+    pub drones_outside_vertical_corridor: Vec<Coordinate3D>,
+    //Landing positions of the drones predicted to land with insufficient battery charge
+    pub drones_low_battery: Vec<Coordinate3D>,
 }
 
 impl SafetyCheckResult {
@@ -84,6 +97,8 @@ impl SafetyCheckResult {
             min_distance: Vec::new(),
             min_altitude: Vec::new(),
             all_close_pairs: Vec::new(),
+            drones_outside_vertical_corridor: Vec::new(),
+            drones_low_battery: Vec::new(),
         }
     }
 
@@ -109,6 +124,8 @@ impl SafetyCheckResult {
             min_distance,
             min_altitude,
             all_close_pairs,
+            drones_outside_vertical_corridor: Vec::new(),
+            drones_low_battery: Vec::new(),
         }
     }
 
@@ -131,9 +148,28 @@ impl SafetyCheckResult {
             min_distance: Vec::new(),
             min_altitude: Vec::new(),
             all_close_pairs,
+            drones_outside_vertical_corridor: Vec::new(),
+            drones_low_battery: Vec::new(),
         }
     }
 
+    /**
+     * Append the contents of another result to the end of this one, keeping the order of
+     * the entries of both results
+     */
+    pub fn extend(&mut self, other: SafetyCheckResult) {
+        self.drones_over_max_altitude.extend(other.drones_over_max_altitude);
+        self.drones_over_max_velocity_xy.extend(other.drones_over_max_velocity_xy);
+        self.drones_over_max_velocity_z.extend(other.drones_over_max_velocity_z);
+        self.drones_below_min_nav_altitude.extend(other.drones_below_min_nav_altitude);
+        self.closest_pair.extend(other.closest_pair);
+        self.min_distance.extend(other.min_distance);
+        self.min_altitude.extend(other.min_altitude);
+        self.all_close_pairs.extend(other.all_close_pairs);
+        self.drones_outside_vertical_corridor.extend(other.drones_outside_vertical_corridor);
+        self.drones_low_battery.extend(other.drones_low_battery);
+    }
+
     pub fn clear(&mut self) {
         self.drones_over_max_altitude.clear();
         self.drones_over_max_velocity_xy.clear();
@@ -143,5 +179,7 @@ impl SafetyCheckResult {
         self.closest_pair.clear();
         self.min_distance.clear();
         self.min_altitude.clear();
+        self.drones_outside_vertical_corridor.clear();
+        self.drones_low_battery.clear();
     }
 }
@@ -0,0 +1,317 @@
+use std::fmt;
+
+//Default resolution of musical positions, as in most MIDI sequencers
+const DEFAULT_TICKS_PER_BEAT: u32 = 480;
+
+/**
+ * Position in musical time, written as bar:beat:tick. Bars and beats start at 1, ticks at 0.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct MusicalPosition {
+    pub bar: u32,
+    pub beat: u32,
+    pub tick: u32,
+}
+
+impl MusicalPosition {
+    pub fn new(bar: u32, beat: u32, tick: u32) -> Self {
+        Self { bar, beat, tick }
+    }
+
+    /** Parses a position written as "bar:beat:tick" or "bar:beat" */
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let fields: Result<Vec<u32>, _> = text.trim().split(':').map(|field| field.trim().parse::<u32>()).collect();
+
+        match fields.as_deref() {
+            Ok([bar, beat]) => Ok(Self::new(*bar, *beat, 0)),
+            Ok([bar, beat, tick]) => Ok(Self::new(*bar, *beat, *tick)),
+            _ => Err("MusicalPosition.parse: The position must be written as bar:beat:tick")
+        }
+    }
+}
+
+impl fmt::Display for MusicalPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.bar, self.beat, self.tick)
+    }
+}
+
+/**
+ * Time signature in effect from the given bar on
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TimeSignature {
+    pub bar: u32,
+    pub beats_per_bar: u32,
+    //Note value of a beat, e.g. 4 for quarter notes; informative only, since tempos are
+    //given in beats per minute
+    pub beat_unit: u32,
+}
+
+/**
+ * Tempo map of the song of a show: tempo changes and time signatures placed in musical
+ * time, used to convert between seconds and bar:beat:tick positions.
+ *
+ * Tempos are constant between changes and are given in beats of the time signature per
+ * minute. Light and trajectory keyframes authored in musical time stay in sync when the
+ * map is corrected by moving them with retime().
+ */
+#[derive(Clone)]
+pub struct TempoMap {
+    //Time of the position 1:1:0, in seconds
+    offset: f32,
+    ticks_per_beat: u32,
+    //Tempo changes as (position, beats per minute), sorted by position; the first is at 1:1:0
+    tempos: Vec<(MusicalPosition, f32)>,
+    //Time signatures sorted by bar; the first is at bar 1
+    signatures: Vec<TimeSignature>,
+}
+
+impl TempoMap {
+    /**
+     * Creates a tempo map with a constant tempo and time signature starting at 0 seconds.
+     *
+     * Returns:
+     *     an error if the tempo is not positive and finite or a bar has no beats
+     */
+    pub fn new(bpm: f32, beats_per_bar: u32, beat_unit: u32) -> Result<Self, &'static str> {
+        if !is_valid_tempo(bpm) { return Err("TempoMap.new: The tempo must be positive") }
+        if beats_per_bar == 0 { return Err("TempoMap.new: A bar must have at least one beat") }
+
+        Ok(Self {
+            offset: 0.0,
+            ticks_per_beat: DEFAULT_TICKS_PER_BEAT,
+            tempos: vec![(MusicalPosition::new(1, 1, 0), bpm)],
+            signatures: vec![TimeSignature { bar: 1, beats_per_bar, beat_unit }],
+        })
+    }
+
+    pub fn get_offset(&self) -> f32 {
+        self.offset
+    }
+
+    /** Set the time of the first beat of the song, in seconds */
+    pub fn set_offset(&mut self, offset: f32) {
+        self.offset = offset;
+    }
+
+    pub fn get_ticks_per_beat(&self) -> u32 {
+        self.ticks_per_beat
+    }
+
+    pub fn set_ticks_per_beat(&mut self, ticks_per_beat: u32) {
+        self.ticks_per_beat = ticks_per_beat.max(1);
+    }
+
+    pub fn get_tempos(&self) -> &[(MusicalPosition, f32)] {
+        &self.tempos
+    }
+
+    pub fn get_signatures(&self) -> &[TimeSignature] {
+        &self.signatures
+    }
+
+    /** Sets the tempo from the given position on, replacing any change at the same position */
+    pub fn set_tempo(&mut self, position: MusicalPosition, bpm: f32) -> Result<(), &'static str> {
+        if !is_valid_tempo(bpm) { return Err("TempoMap.set_tempo: The tempo must be positive") }
+        if position < MusicalPosition::new(1, 1, 0) { return Err("TempoMap.set_tempo: Positions start at 1:1:0") }
+
+        match self.tempos.binary_search_by(|tempo| tempo.0.cmp(&position)) {
+            Ok(index) => self.tempos[index].1 = bpm,
+            Err(index) => self.tempos.insert(index, (position, bpm))
+        }
+        Ok(())
+    }
+
+    /** Sets the time signature from the given bar on, replacing any change at the same bar */
+    pub fn set_time_signature(&mut self, bar: u32, beats_per_bar: u32, beat_unit: u32) -> Result<(), &'static str> {
+        if beats_per_bar == 0 { return Err("TempoMap.set_time_signature: A bar must have at least one beat") }
+        if bar == 0 { return Err("TempoMap.set_time_signature: Bars start at 1") }
+
+        let signature = TimeSignature { bar, beats_per_bar, beat_unit };
+        match self.signatures.binary_search_by(|other| other.bar.cmp(&bar)) {
+            Ok(index) => self.signatures[index] = signature,
+            Err(index) => self.signatures.insert(index, signature)
+        }
+        Ok(())
+    }
+
+    /** Return the time of a musical position, in seconds */
+    pub fn seconds_at(&self, position: MusicalPosition) -> f32 {
+        self.beats_to_seconds(self.beats_at(position))
+    }
+
+    /**
+     * Return the musical position at the given time, rounded to the nearest tick. Times
+     * before the first beat of the song are clamped to 1:1:0.
+     */
+    pub fn position_at(&self, t: f32) -> MusicalPosition {
+        let ticks = (self.seconds_to_beats(t) * self.ticks_per_beat as f32).round().max(0.0) as u64;
+        let tick = (ticks % self.ticks_per_beat as u64) as u32;
+        let mut beats = ticks / self.ticks_per_beat as u64;
+
+        for (index, signature) in self.signatures.iter().enumerate() {
+            let bar_beats = signature.beats_per_bar as u64;
+            if let Some(next) = self.signatures.get(index + 1) {
+                let section_beats = (next.bar - signature.bar) as u64 * bar_beats;
+                if beats >= section_beats {
+                    beats -= section_beats;
+                    continue;
+                }
+            }
+
+            return MusicalPosition::new(signature.bar + (beats / bar_beats) as u32, (beats % bar_beats) as u32 + 1, tick);
+        }
+
+        MusicalPosition::new(1, 1, tick)
+    }
+
+    /** Return the number of beats from 1:1:0 to a musical position */
+    pub fn beats_at(&self, position: MusicalPosition) -> f32 {
+        let bar = position.bar.max(1);
+        let mut beats = 0;
+
+        for (index, signature) in self.signatures.iter().enumerate() {
+            if signature.bar >= bar { break }
+
+            let section_end = self.signatures.get(index + 1).map_or(bar, |next| next.bar.min(bar));
+            beats += (section_end - signature.bar) * signature.beats_per_bar;
+        }
+
+        (beats + position.beat.saturating_sub(1)) as f32 + position.tick as f32 / self.ticks_per_beat as f32
+    }
+
+    /** Return the time of the given number of beats after 1:1:0, in seconds */
+    pub fn beats_to_seconds(&self, beats: f32) -> f32 {
+        let sections = self.tempo_sections();
+        let (start_beats, start_seconds, bpm) = sections.iter()
+            .rev()
+            .find(|section| section.0 <= beats)
+            .unwrap_or(&sections[0]);
+
+        self.offset + start_seconds + (beats - start_beats) * 60.0 / bpm
+    }
+
+    /** Return the number of beats from 1:1:0 to the given time; negative before it */
+    pub fn seconds_to_beats(&self, t: f32) -> f32 {
+        let elapsed = t - self.offset;
+        let sections = self.tempo_sections();
+        let (start_beats, start_seconds, bpm) = sections.iter()
+            .rev()
+            .find(|section| section.1 <= elapsed)
+            .unwrap_or(&sections[0]);
+
+        start_beats + (elapsed - start_seconds) * bpm / 60.0
+    }
+
+    /**
+     * Snaps a time to the nearest subdivision of a beat.
+     *
+     * Parameters:
+     *     t: the time in seconds
+     *     division: number of subdivisions per beat, e.g. 1 for beats or 4 for sixteenth notes in 4/4
+     */
+    pub fn snap(&self, t: f32, division: u32) -> f32 {
+        let division = division.max(1) as f32;
+        self.beats_to_seconds((self.seconds_to_beats(t) * division).round() / division)
+    }
+
+    /**
+     * Moves a time authored with this tempo map to the same musical position in a corrected
+     * tempo map. Use it with LightProgram.retime() and Trajectory.retime().
+     */
+    pub fn retime(&self, t: f32, corrected: &TempoMap) -> f32 {
+        corrected.beats_to_seconds(self.seconds_to_beats(t))
+    }
+
+    /* Tempo sections as (start in beats, start in seconds after the offset, beats per minute) */
+    fn tempo_sections(&self) -> Vec<(f32, f32, f32)> {
+        let mut sections: Vec<(f32, f32, f32)> = Vec::with_capacity(self.tempos.len());
+
+        for (position, bpm) in &self.tempos {
+            let start = self.beats_at(*position);
+            let seconds = match sections.last() {
+                Some((previous_start, previous_seconds, previous_bpm)) => previous_seconds + (start - previous_start) * 60.0 / previous_bpm,
+                None => 0.0
+            };
+            sections.push((start, seconds, *bpm));
+        }
+
+        sections
+    }
+}
+
+/* Tempos must be positive and finite; NaN fails the comparison */
+fn is_valid_tempo(bpm: f32) -> bool {
+    bpm > 0.0 && bpm.is_finite()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* 120 BPM in 4/4 from 0.5 s, 60 BPM from bar 3 and 3/4 from bar 5 */
+    fn song() -> TempoMap {
+        let mut map = TempoMap::new(120.0, 4, 4).unwrap();
+        map.set_offset(0.5);
+        map.set_tempo(MusicalPosition::new(3, 1, 0), 60.0).unwrap();
+        map.set_time_signature(5, 3, 4).unwrap();
+        map
+    }
+
+    #[test]
+    fn invalid_tempos_are_rejected() {
+        for bpm in [0.0, -120.0, f32::NAN, f32::INFINITY] {
+            assert!(TempoMap::new(bpm, 4, 4).is_err());
+            assert!(TempoMap::new(120.0, 4, 4).unwrap().set_tempo(MusicalPosition::new(2, 1, 0), bpm).is_err());
+        }
+        assert!(TempoMap::new(120.0, 0, 4).is_err());
+        assert!(TempoMap::new(120.0, 4, 4).unwrap().set_time_signature(0, 4, 4).is_err());
+    }
+
+    #[test]
+    fn parse_positions() {
+        assert_eq!(MusicalPosition::parse(" 3:2:240 "), Ok(MusicalPosition::new(3, 2, 240)));
+        assert_eq!(MusicalPosition::parse("3:2"), Ok(MusicalPosition::new(3, 2, 0)));
+        assert!(MusicalPosition::parse("3").is_err());
+        assert!(MusicalPosition::parse("3:x:0").is_err());
+        assert_eq!(MusicalPosition::new(3, 2, 240).to_string(), "3:2:240");
+    }
+
+    #[test]
+    fn seconds_and_positions_across_tempo_and_signature_changes() {
+        let map = song();
+        let cases = [
+            (MusicalPosition::new(1, 1, 0), 0.5),
+            (MusicalPosition::new(2, 3, 120), 3.625),
+            //First beat at 60 BPM
+            (MusicalPosition::new(3, 1, 0), 4.5),
+            (MusicalPosition::new(4, 4, 240), 12.0),
+            //First bar in 3/4
+            (MusicalPosition::new(5, 1, 0), 12.5),
+            (MusicalPosition::new(6, 1, 0), 15.5),
+            (MusicalPosition::new(6, 2, 240), 17.0),
+        ];
+
+        for (position, t) in cases {
+            assert!((map.seconds_at(position) - t).abs() < 1e-5, "{} at {} s", position, map.seconds_at(position));
+            assert_eq!(map.position_at(t), position);
+        }
+        assert_eq!(map.position_at(0.0), MusicalPosition::new(1, 1, 0));
+    }
+
+    #[test]
+    fn snap_and_retime() {
+        let map = song();
+
+        assert_eq!(map.snap(0.8, 1), 1.0);
+        assert_eq!(map.snap(4.6, 4), 4.5);
+        assert_eq!(map.snap(13.3, 2), 13.5);
+
+        let mut corrected = map.clone();
+        corrected.set_offset(1.0);
+        corrected.set_tempo(MusicalPosition::new(1, 1, 0), 60.0).unwrap();
+        assert_eq!(map.retime(4.5, &corrected), 9.0);
+        assert_eq!(map.retime(12.5, &corrected), 17.0);
+    }
+}
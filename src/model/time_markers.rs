@@ -1,31 +1,645 @@
 use std::collections::HashMap;
 
+//Suffixes of the pair of Blender markers that represent the start and end of a range
+const RANGE_START_SUFFIX: &str = "_start";
+const RANGE_END_SUFFIX: &str = "_end";
+
 /**
  * Struct that represent a Time Marker List
  */
-struct TimeMarkers {
+pub struct TimeMarkers {
     /** The hashmap of time markers where keys represent marker names and
      * values represent time in seconds
      */
-    markers: HashMap<String, f32>
+    markers: HashMap<String, f32>,
+    //Named time ranges, as (start, end) in seconds
+    ranges: HashMap<String, (f32, f32)>
 }
 
 impl TimeMarkers {
     pub fn new() -> Self {
         Self {
-            markers: HashMap::new()
+            markers: HashMap::new(),
+            ranges: HashMap::new()
         }
     }
 
     pub fn new_from(markers: HashMap<String, f32>) -> Self {
-        Self { markers }
+        Self { markers, ranges: HashMap::new() }
     }
 
     pub fn append(&mut self, key: String, value: f32) {
+        if !value.is_finite() || !is_valid_name(&key) {
+            eprintln!("TimeMarkers.append: Markers need a finite time and a name without line breaks");
+            return;
+        }
         self.markers.insert(key, value);
     }
 
     pub fn delete(&mut self, key: &String ) {
         self.markers.remove(key);
     }
-}
\ No newline at end of file
+
+    /**
+     * Add a named time range; start and end are swapped if given in the wrong order.
+     *
+     * Returns:
+     *     an error if a time is not finite or the name has line breaks
+     */
+    pub fn append_range(&mut self, key: String, start: f32, end: f32) -> Result<(), &'static str> {
+        if !start.is_finite() || !end.is_finite() { return Err("TimeMarkers.append_range: The start and end times must be finite") }
+        if !is_valid_name(&key) { return Err("TimeMarkers.append_range: Names can't have line breaks") }
+
+        self.ranges.insert(key, (start.min(end), start.max(end)));
+        Ok(())
+    }
+
+    pub fn delete_range(&mut self, key: &String) {
+        self.ranges.remove(key);
+    }
+
+    /** Return the time of the marker with the given name, in seconds */
+    pub fn get(&self, key: &str) -> Option<f32> {
+        self.markers.get(key).copied()
+    }
+
+    /** Return the (start, end) times of the range with the given name, in seconds */
+    pub fn get_range(&self, key: &str) -> Option<(f32, f32)> {
+        self.ranges.get(key).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.markers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.markers.is_empty()
+    }
+
+    /** Return the markers as (name, time) sorted by time, markers at the same time by name */
+    pub fn sorted(&self) -> Vec<(&str, f32)> {
+        let mut markers: Vec<(&str, f32)> = self.markers.iter().map(|(name, t)| (name.as_str(), *t)).collect();
+        markers.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(b.0)));
+
+        markers
+    }
+
+    /** Return the ranges as (name, start, end) sorted by start time, then by name */
+    pub fn sorted_ranges(&self) -> Vec<(&str, f32, f32)> {
+        let mut ranges: Vec<(&str, f32, f32)> = self.ranges.iter()
+            .map(|(name, (start, end))| (name.as_str(), *start, *end))
+            .collect();
+        ranges.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(b.0)));
+
+        ranges
+    }
+
+    /**
+     * Return the latest marker at or before the given time, if any. Of several markers at
+     * that time, the first by name is returned.
+     */
+    pub fn before(&self, t: f32) -> Option<(&str, f32)> {
+        latest_until(&self.sorted(), t)
+    }
+
+    /**
+     * Return the earliest marker at or after the given time, if any. Of several markers at
+     * that time, the first by name is returned.
+     */
+    pub fn after(&self, t: f32) -> Option<(&str, f32)> {
+        earliest_from(&self.sorted(), t)
+    }
+
+    /** Return the marker closest to the given time, if any. Ties go to the earlier marker. */
+    pub fn nearest(&self, t: f32) -> Option<(&str, f32)> {
+        let markers = self.sorted();
+
+        match (latest_until(&markers, t), earliest_from(&markers, t)) {
+            (Some(before), Some(after)) => Some(if t - before.1 <= after.1 - t { before } else { after }),
+            (before, after) => before.or(after)
+        }
+    }
+
+    /** Return the markers between two times, both included, sorted by time */
+    pub fn within(&self, start: f32, end: f32) -> Vec<(&str, f32)> {
+        if start.is_nan() || end.is_nan() { return Vec::new() }
+
+        let markers = self.sorted();
+        let first = markers.partition_point(|marker| marker.1 < start);
+        let last = markers.partition_point(|marker| marker.1 <= end);
+
+        markers[first..last.max(first)].to_vec()
+    }
+
+    /** Return the ranges that contain the given time, both ends included, sorted by start time */
+    pub fn ranges_at(&self, t: f32) -> Vec<(&str, f32, f32)> {
+        let mut ranges = self.sorted_ranges();
+        //Ranges are sorted by start time, so only a prefix of them can have started
+        ranges.truncate(ranges.partition_point(|range| range.1 <= t));
+        ranges.retain(|range| t <= range.2);
+
+        ranges
+    }
+
+    /**
+     * Changes the name of a marker.
+     *
+     * Returns:
+     *     an error if there is no marker with the old name, one with the new name exists or
+     *     the new name has line breaks
+     */
+    pub fn rename(&mut self, old_key: &str, new_key: String) -> Result<(), &'static str> {
+        if !is_valid_name(&new_key) { return Err("TimeMarkers.rename: Names can't have line breaks") }
+        if self.markers.contains_key(&new_key) { return Err("TimeMarkers.rename: A marker with the new name already exists") }
+
+        let t = self.markers.remove(old_key).ok_or("TimeMarkers.rename: There is no marker with the given name")?;
+        self.markers.insert(new_key, t);
+        Ok(())
+    }
+
+    /** Changes the name of a range. See rename() for the errors. */
+    pub fn rename_range(&mut self, old_key: &str, new_key: String) -> Result<(), &'static str> {
+        if !is_valid_name(&new_key) { return Err("TimeMarkers.rename_range: Names can't have line breaks") }
+        if self.ranges.contains_key(&new_key) { return Err("TimeMarkers.rename_range: A range with the new name already exists") }
+
+        let range = self.ranges.remove(old_key).ok_or("TimeMarkers.rename_range: There is no range with the given name")?;
+        self.ranges.insert(new_key, range);
+        Ok(())
+    }
+
+    /**
+     * Moves every marker at or after the given time, e.g. when a section is inserted in the
+     * song. Ranges starting at or after that time are moved as well, and ranges containing it
+     * are stretched. A negative delta removes time, but never makes a range end before it
+     * starts.
+     *
+     * Parameters:
+     *     t: the time where the section is inserted, in seconds
+     *     delta: the duration of the inserted section, in seconds
+     */
+    pub fn shift_after(&mut self, t: f32, delta: f32) {
+        for time in self.markers.values_mut().filter(|time| **time >= t) {
+            *time += delta;
+        }
+
+        for (start, end) in self.ranges.values_mut() {
+            if *start >= t {
+                *start += delta;
+            }
+            if *end >= t {
+                *end = (*end + delta).max(*start);
+            }
+        }
+    }
+
+    /**
+     * Parses an Audacity label track exported as text. Each line holds the start time, the
+     * end time and the name of a label separated by tabs; labels with different start and
+     * end times become ranges. Repeated names get a numeric suffix, as in Blender.
+     */
+    pub fn from_audacity_labels(text: &str) -> Result<Self, &'static str> {
+        let mut markers = TimeMarkers::new();
+
+        for line in text.lines() {
+            //Lines starting with a backslash hold the frequency range of the previous label
+            if line.trim().is_empty() || line.starts_with('\\') { continue }
+
+            let mut fields = line.splitn(3, '\t');
+            let (start, end) = match (parse_time(fields.next()), parse_time(fields.next())) {
+                (Some(start), Some(end)) => (start, end),
+                _ => return Err("TimeMarkers.from_audacity_labels: Every label needs a finite start and end time")
+            };
+            let name = fields.next().unwrap_or("").trim_end_matches('\r').to_string();
+            if !is_valid_name(&name) { return Err("TimeMarkers.from_audacity_labels: Names can't have line breaks") }
+
+            if start == end {
+                let name = markers.unique_marker_name(name);
+                markers.append(name, start);
+            } else {
+                let name = markers.unique_range_name(name);
+                markers.append_range(name, start, end)?;
+            }
+        }
+
+        Ok(markers)
+    }
+
+    /** Exports the markers and ranges as an Audacity label track, sorted by time */
+    pub fn as_audacity_labels(&self) -> String {
+        self.sorted_entries()
+            .iter()
+            .map(|(name, start, end)| format!("{:.6}\t{:.6}\t{}\n", start, end.unwrap_or(*start), name))
+            .collect()
+    }
+
+    /**
+     * Parses a CSV cue sheet where each row holds a name and a time in seconds, plus an end
+     * time for ranges. A header row is skipped; names may be quoted, but can't have line
+     * breaks.
+     */
+    pub fn from_csv(text: &str) -> Result<Self, &'static str> {
+        let mut markers = TimeMarkers::new();
+
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() { continue }
+
+            let fields = split_csv_line(line.trim_end_matches('\r'));
+            let start = match fields.get(1).map(|field| field.trim().parse::<f32>()) {
+                Some(Ok(start)) if start.is_finite() => start,
+                Some(Err(_)) | None if index == 0 => continue,
+                _ => return Err("TimeMarkers.from_csv: Every row needs a name and a finite time")
+            };
+            let name = fields[0].clone();
+            //An unbalanced quote means the name continues on the next line
+            if line.matches('"').count() % 2 == 1 { return Err("TimeMarkers.from_csv: Names can't have line breaks") }
+
+            match fields.get(2).map(|field| field.trim()).filter(|field| !field.is_empty()) {
+                Some(end) => {
+                    let end = parse_time(Some(end)).ok_or("TimeMarkers.from_csv: Invalid end time")?;
+                    let name = markers.unique_range_name(name);
+                    markers.append_range(name, start, end)?;
+                },
+                None => {
+                    let name = markers.unique_marker_name(name);
+                    markers.append(name, start);
+                }
+            }
+        }
+
+        Ok(markers)
+    }
+
+    /** Exports the markers and ranges as a CSV cue sheet with a header row, sorted by time */
+    pub fn as_csv(&self) -> String {
+        let mut csv = String::from("name,time,end\n");
+
+        for (name, start, end) in self.sorted_entries() {
+            let end = end.map(|end| end.to_string()).unwrap_or_default();
+            csv.push_str(&format!("{},{},{}\n", quote_csv_field(&name), start, end));
+        }
+
+        csv
+    }
+
+    /**
+     * Creates the markers from Blender timeline markers, given as names and frame numbers.
+     * Pairs of markers named "<name>_start" and "<name>_end" become a range.
+     *
+     * Parameters:
+     *     markers: the name and frame of each marker
+     *     fps: the frame rate of the Blender scene
+     */
+    pub fn from_blender_markers(markers: &[(String, i32)], fps: f32) -> Result<Self, &'static str> {
+        if fps <= 0.0 { return Err("TimeMarkers.from_blender_markers: The frame rate must be positive") }
+
+        if markers.iter().any(|(name, _)| !is_valid_name(name)) { return Err("TimeMarkers.from_blender_markers: Names can't have line breaks") }
+
+        let frames: HashMap<&str, i32> = markers.iter().map(|(name, frame)| (name.as_str(), *frame)).collect();
+        let mut result = TimeMarkers::new();
+
+        for (name, frame) in markers {
+            if let Some(range) = name.strip_suffix(RANGE_START_SUFFIX) {
+                if let Some(end) = frames.get(format!("{}{}", range, RANGE_END_SUFFIX).as_str()) {
+                    result.append_range(range.to_string(), *frame as f32 / fps, *end as f32 / fps)?;
+                    continue;
+                }
+            }
+            if let Some(range) = name.strip_suffix(RANGE_END_SUFFIX) {
+                if frames.contains_key(format!("{}{}", range, RANGE_START_SUFFIX).as_str()) { continue }
+            }
+
+            result.append(name.clone(), *frame as f32 / fps);
+        }
+
+        Ok(result)
+    }
+
+    /**
+     * Exports the markers as Blender timeline markers, rounding each time to the nearest
+     * frame. Each range becomes a pair of markers named "<name>_start" and "<name>_end".
+     *
+     * Returns:
+     *     the name and frame of each marker, sorted by frame
+     */
+    pub fn as_blender_markers(&self, fps: f32) -> Vec<(String, i32)> {
+        let frame = |t: f32| (t * fps).round() as i32;
+        let mut markers: Vec<(String, i32)> = Vec::new();
+
+        for (name, start, end) in self.sorted_entries() {
+            match end {
+                Some(end) => {
+                    markers.push((format!("{}{}", name, RANGE_START_SUFFIX), frame(start)));
+                    markers.push((format!("{}{}", name, RANGE_END_SUFFIX), frame(end)));
+                },
+                None => markers.push((name, frame(start)))
+            }
+        }
+        markers.sort_by_key(|marker| marker.1);
+
+        markers
+    }
+
+    /*
+     * Markers and ranges as (name, start, end), where only ranges have an end, sorted by
+     * start time and then by name
+     */
+    fn sorted_entries(&self) -> Vec<(String, f32, Option<f32>)> {
+        let mut entries: Vec<(String, f32, Option<f32>)> = self.markers.iter()
+            .map(|(name, t)| (name.clone(), *t, None))
+            .chain(self.ranges.iter().map(|(name, (start, end))| (name.clone(), *start, Some(*end))))
+            .collect();
+        entries.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+
+        entries
+    }
+
+    /* Returns the name, or the name with the first free ".001"-like suffix if already used */
+    fn unique_marker_name(&self, name: String) -> String {
+        unique_name(name, |candidate| self.markers.contains_key(candidate))
+    }
+
+    fn unique_range_name(&self, name: String) -> String {
+        unique_name(name, |candidate| self.ranges.contains_key(candidate))
+    }
+}
+
+/* Latest marker at or before t, from markers sorted by time and then by name */
+fn latest_until<'a>(markers: &[(&'a str, f32)], t: f32) -> Option<(&'a str, f32)> {
+    let time = markers[..markers.partition_point(|marker| marker.1 <= t)].last()?.1;
+    Some(markers[markers.partition_point(|marker| marker.1 < time)])
+}
+
+/* Earliest marker at or after t, from markers sorted by time and then by name */
+fn earliest_from<'a>(markers: &[(&'a str, f32)], t: f32) -> Option<(&'a str, f32)> {
+    if t.is_nan() { return None }
+
+    markers.get(markers.partition_point(|marker| marker.1 < t)).copied()
+}
+
+fn unique_name(name: String, is_used: impl Fn(&str) -> bool) -> String {
+    if !is_used(&name) { return name }
+
+    (1..)
+        .map(|index| format!("{}.{:03}", name, index))
+        .find(|candidate| !is_used(candidate))
+        .unwrap()
+}
+
+/* Parses a time in seconds, which must be finite */
+fn parse_time(field: Option<&str>) -> Option<f32> {
+    field
+        .and_then(|field| field.trim().parse::<f32>().ok())
+        .filter(|t| t.is_finite())
+}
+
+/* Names are stored one per line by the label and CSV formats */
+fn is_valid_name(name: &str) -> bool {
+    !name.contains(['\n', '\r'])
+}
+
+/* Splits a CSV line into fields, removing the quotes around quoted fields */
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields: Vec<String> = vec![String::new()];
+    let mut is_quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if is_quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            },
+            '"' => is_quoted = !is_quoted,
+            ',' if !is_quoted => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c)
+        }
+    }
+
+    fields
+}
+
+fn quote_csv_field(field: &str) -> String {
+    if field.contains([',', '"']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_markers() -> TimeMarkers {
+        let mut markers = TimeMarkers::new();
+        markers.append("intro".to_string(), 0.0);
+        markers.append("drop, \"big\"".to_string(), 12.5);
+        markers.append("outro".to_string(), 61.25);
+        markers.append_range("chorus".to_string(), 20.0, 35.5).unwrap();
+        markers.append_range("bridge".to_string(), 40.0, 48.0).unwrap();
+        markers
+    }
+
+    fn assert_same_markers(actual: &TimeMarkers, expected: &TimeMarkers) {
+        assert_eq!(actual.sorted(), expected.sorted());
+        assert_eq!(actual.sorted_ranges(), expected.sorted_ranges());
+    }
+
+    #[test]
+    fn audacity_round_trip() {
+        let markers = sample_markers();
+        let labels = markers.as_audacity_labels();
+
+        assert!(labels.starts_with("0.000000\t0.000000\tintro\n"));
+        assert!(labels.contains("20.000000\t35.500000\tchorus\n"));
+        assert_same_markers(&TimeMarkers::from_audacity_labels(&labels).unwrap(), &markers);
+    }
+
+    #[test]
+    fn audacity_labels_with_frequency_lines_and_repeated_names() {
+        let labels = "1.5\t1.5\thit\r\n\\\t100.0\t2000.0\r\n3.0\t3.0\thit\r\n4.0\t6.0\tsolo\r\n";
+        let markers = TimeMarkers::from_audacity_labels(labels).unwrap();
+
+        assert_eq!(markers.sorted(), vec![("hit", 1.5), ("hit.001", 3.0)]);
+        assert_eq!(markers.get_range("solo"), Some((4.0, 6.0)));
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let markers = sample_markers();
+        let csv = markers.as_csv();
+
+        assert!(csv.starts_with("name,time,end\nintro,0,\n\"drop, \"\"big\"\"\",12.5,\n"));
+        assert_same_markers(&TimeMarkers::from_csv(&csv).unwrap(), &markers);
+    }
+
+    #[test]
+    fn blender_round_trip() {
+        let markers = sample_markers();
+        let blender = markers.as_blender_markers(24.0);
+
+        assert!(blender.contains(&("chorus_start".to_string(), 480)));
+        assert!(blender.contains(&("chorus_end".to_string(), 852)));
+        assert_same_markers(&TimeMarkers::from_blender_markers(&blender, 24.0).unwrap(), &markers);
+    }
+
+    #[test]
+    fn blender_times_are_rounded_to_frames() {
+        let mut markers = TimeMarkers::new();
+        markers.append("hit".to_string(), 1.02);
+        let blender = markers.as_blender_markers(25.0);
+
+        assert_eq!(blender, vec![("hit".to_string(), 26)]);
+        assert_eq!(TimeMarkers::from_blender_markers(&blender, 25.0).unwrap().get("hit"), Some(1.04));
+        assert!(TimeMarkers::from_blender_markers(&blender, 0.0).is_err());
+    }
+
+    #[test]
+    fn non_finite_times_are_rejected() {
+        assert!(TimeMarkers::from_audacity_labels("nan\tnan\thit\n").is_err());
+        assert!(TimeMarkers::from_audacity_labels("1.0\tinf\tsolo\n").is_err());
+        assert!(TimeMarkers::from_csv("name,time,end\nhit,NaN,\n").is_err());
+        assert!(TimeMarkers::from_csv("hit,inf,\n").is_err());
+        assert!(TimeMarkers::from_csv("name,time,end\nsolo,1.0,-inf\n").is_err());
+
+        let mut markers = TimeMarkers::new();
+        assert!(markers.append_range("solo".to_string(), 0.0, f32::INFINITY).is_err());
+        markers.append("hit".to_string(), f32::NAN);
+        assert!(markers.is_empty());
+        assert!(markers.get_range("solo").is_none());
+    }
+
+    #[test]
+    fn names_with_line_breaks_are_rejected() {
+        assert!(TimeMarkers::from_csv("name,time,end\n\"two\nlines\",1.0,\n").is_err());
+        assert!(TimeMarkers::from_audacity_labels("1.0\t1.0\ttwo\rlines\n").is_err());
+        assert!(TimeMarkers::from_blender_markers(&[("two\nlines".to_string(), 24)], 24.0).is_err());
+
+        let mut markers = sample_markers();
+        assert!(markers.append_range("two\nlines".to_string(), 0.0, 1.0).is_err());
+        assert!(markers.rename("intro", "two\nlines".to_string()).is_err());
+        assert!(markers.rename_range("chorus", "two\r\nlines".to_string()).is_err());
+        markers.append("two\nlines".to_string(), 1.0);
+        assert_same_markers(&markers, &sample_markers());
+    }
+
+    #[test]
+    fn before_and_after_include_the_given_time() {
+        let markers = sample_markers();
+
+        assert_eq!(markers.before(12.5), Some(("drop, \"big\"", 12.5)));
+        assert_eq!(markers.before(12.4), Some(("intro", 0.0)));
+        assert_eq!(markers.before(-0.1), None);
+        assert_eq!(markers.after(12.5), Some(("drop, \"big\"", 12.5)));
+        assert_eq!(markers.after(12.6), Some(("outro", 61.25)));
+        assert_eq!(markers.after(61.3), None);
+        assert_eq!(markers.before(f32::NAN), None);
+        assert_eq!(markers.after(f32::NAN), None);
+    }
+
+    #[test]
+    fn nearest_breaks_ties_towards_the_earlier_marker() {
+        let mut markers = TimeMarkers::new();
+        assert_eq!(markers.nearest(1.0), None);
+
+        markers.append("a".to_string(), 10.0);
+        markers.append("c".to_string(), 20.0);
+        markers.append("b".to_string(), 20.0);
+
+        assert_eq!(markers.nearest(15.0), Some(("a", 10.0)));
+        assert_eq!(markers.nearest(14.9), Some(("a", 10.0)));
+        assert_eq!(markers.nearest(15.1), Some(("b", 20.0)));
+        //Markers at the same time are taken by name, whichever side they are found from
+        assert_eq!(markers.nearest(20.0), Some(("b", 20.0)));
+        assert_eq!(markers.nearest(30.0), Some(("b", 20.0)));
+        assert_eq!(markers.nearest(-5.0), Some(("a", 10.0)));
+        assert_eq!(markers.nearest(f32::NAN), None);
+    }
+
+    #[test]
+    fn within_includes_both_ends() {
+        let markers = sample_markers();
+
+        assert_eq!(markers.within(0.0, 12.5), vec![("intro", 0.0), ("drop, \"big\"", 12.5)]);
+        assert_eq!(markers.within(12.5, 12.5), vec![("drop, \"big\"", 12.5)]);
+        assert_eq!(markers.within(0.1, 12.4), Vec::new());
+        assert_eq!(markers.within(20.0, 10.0), Vec::new());
+        assert_eq!(markers.within(f32::NAN, 100.0), Vec::new());
+        assert_eq!(markers.within(f32::NEG_INFINITY, f32::INFINITY).len(), 3);
+    }
+
+    #[test]
+    fn ranges_at_includes_both_ends() {
+        let mut markers = sample_markers();
+        markers.append_range("verse".to_string(), 30.0, 40.0).unwrap();
+
+        assert_eq!(markers.ranges_at(19.9), Vec::new());
+        assert_eq!(markers.ranges_at(20.0), vec![("chorus", 20.0, 35.5)]);
+        assert_eq!(markers.ranges_at(35.5), vec![("chorus", 20.0, 35.5), ("verse", 30.0, 40.0)]);
+        assert_eq!(markers.ranges_at(40.0), vec![("verse", 30.0, 40.0), ("bridge", 40.0, 48.0)]);
+        assert_eq!(markers.ranges_at(48.0), vec![("bridge", 40.0, 48.0)]);
+        assert_eq!(markers.ranges_at(48.1), Vec::new());
+        assert_eq!(markers.ranges_at(f32::NAN), Vec::new());
+    }
+
+    #[test]
+    fn shift_after_moves_later_markers_and_stretches_straddling_ranges() {
+        let mut markers = TimeMarkers::new();
+        markers.append("before".to_string(), 5.0);
+        markers.append("at".to_string(), 10.0);
+        markers.append_range("earlier".to_string(), 0.0, 5.0).unwrap();
+        markers.append_range("ending".to_string(), 5.0, 10.0).unwrap();
+        markers.append_range("straddling".to_string(), 5.0, 15.0).unwrap();
+        markers.append_range("starting".to_string(), 10.0, 12.0).unwrap();
+        markers.shift_after(10.0, 2.0);
+
+        assert_eq!(markers.sorted(), vec![("before", 5.0), ("at", 12.0)]);
+        assert_eq!(markers.get_range("earlier"), Some((0.0, 5.0)));
+        assert_eq!(markers.get_range("ending"), Some((5.0, 12.0)));
+        assert_eq!(markers.get_range("straddling"), Some((5.0, 17.0)));
+        assert_eq!(markers.get_range("starting"), Some((12.0, 14.0)));
+    }
+
+    #[test]
+    fn shift_after_removing_time_never_inverts_ranges() {
+        let mut markers = TimeMarkers::new();
+        markers.append("at".to_string(), 10.0);
+        markers.append_range("straddling".to_string(), 5.0, 15.0).unwrap();
+        markers.append_range("short".to_string(), 8.0, 12.0).unwrap();
+        markers.shift_after(10.0, -8.0);
+
+        assert_eq!(markers.get("at"), Some(2.0));
+        assert_eq!(markers.get_range("straddling"), Some((5.0, 7.0)));
+        assert_eq!(markers.get_range("short"), Some((8.0, 8.0)));
+    }
+
+    #[test]
+    fn rename_marker() {
+        let mut markers = sample_markers();
+
+        assert!(markers.rename("intro", "opening".to_string()).is_ok());
+        assert_eq!(markers.get("intro"), None);
+        assert_eq!(markers.get("opening"), Some(0.0));
+        assert!(markers.rename("intro", "start".to_string()).is_err());
+        assert!(markers.rename("opening", "outro".to_string()).is_err());
+        assert_eq!(markers.get("opening"), Some(0.0));
+        assert_eq!(markers.get("outro"), Some(61.25));
+        //Ranges and markers have separate names
+        assert!(markers.rename("outro", "chorus".to_string()).is_ok());
+        assert_eq!(markers.len(), 3);
+    }
+
+    #[test]
+    fn rename_range() {
+        let mut markers = sample_markers();
+
+        assert!(markers.rename_range("chorus", "refrain".to_string()).is_ok());
+        assert_eq!(markers.get_range("chorus"), None);
+        assert_eq!(markers.get_range("refrain"), Some((20.0, 35.5)));
+        assert!(markers.rename_range("chorus", "hook".to_string()).is_err());
+        assert!(markers.rename_range("refrain", "bridge".to_string()).is_err());
+        assert_eq!(markers.get_range("refrain"), Some((20.0, 35.5)));
+        assert_eq!(markers.get_range("bridge"), Some((40.0, 48.0)));
+        assert!(markers.rename_range("bridge", "intro".to_string()).is_ok());
+    }
+}
@@ -8,7 +8,8 @@ use super::point::{Point3D, Point4D};
  * Positions between given Point4D elements are assumed to be linearly
  * interpolated both in space and time
  */
- struct Trajectory {
+ #[derive(Clone)]
+ pub struct Trajectory {
     points: Vec<Point4D>
 }
 
@@ -29,6 +30,55 @@ impl Trajectory {
         }
     }
 
+    /** Return the points of the trajectory, sorted by time */
+    pub fn get_points(&self) -> &[Point4D] {
+        &self.points
+    }
+
+    /** Return the timestamp of the first point of the trajectory, if any */
+    pub fn start_time(&self) -> Option<f32> {
+        self.points.first().map(|point| point.get_t())
+    }
+
+    /** Return the timestamp of the last point of the trajectory, if any */
+    pub fn end_time(&self) -> Option<f32> {
+        self.points.last().map(|point| point.get_t())
+    }
+
+    /**
+     * Return the position of the trajectory at the given time.
+     * 
+     * Positions between points are linearly interpolated. Before the first point and after
+     * the last one the trajectory is assumed to stay still at its first and last position.
+     * 
+     * Parameters:
+     *     t: the time in seconds
+     * 
+     * Returns:
+     *     the position at the given time, or None if the trajectory has no points
+     */
+    pub fn position_at(&self, t: f32) -> Option<Point3D> {
+        let first = self.points.first()?;
+        let last = self.points.last()?;
+
+        if t <= first.get_t() { return Some(first.as_3d()) }
+        if t >= last.get_t() { return Some(last.as_3d()) }
+
+        //Index of the first point strictly after t; it is always in [1, len - 1] here
+        let index = self.points.partition_point(|point| point.get_t() <= t);
+        let prev = self.points[index - 1];
+        let next = self.points[index];
+        let dt = next.get_t() - prev.get_t();
+        if dt <= 0.0 { return Some(next.as_3d()) }
+
+        let ratio = (t - prev.get_t()) / dt;
+        Some(Point3D::new(
+            prev.get_x() + ratio * (next.get_x() - prev.get_x()),
+            prev.get_y() + ratio * (next.get_y() - prev.get_y()),
+            prev.get_z() + ratio * (next.get_z() - prev.get_z())
+        ))
+    }
+
     /** Create a data format element */
     pub fn as_skyc_format_data(&self, version: u8) -> CvDataFormat {
         CvDataFormat::SkyCvDataFormat(&self.points, version)
@@ -81,6 +131,16 @@ impl Trajectory {
         Self { points: delta_points }
     }
 
+    /**
+     * Returns a copy of the trajectory with the timestamp of every point changed by the given
+     * function, e.g. to snap points to beats or to follow a corrected tempo map.
+     */
+    pub fn retime(&self, map: impl Fn(f32) -> f32) -> Self {
+        Trajectory::new(self.points.iter()
+            .map(|point| Point4D::new(map(point.get_t()), point.get_x(), point.get_y(), point.get_z()))
+            .collect())
+    }
+
     pub fn simplify_in_place(&self) -> Self {
         if self.points.len() == 0 { return Self {points: self.points.clone()} }
 
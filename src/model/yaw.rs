@@ -1,6 +1,17 @@
 use std::iter::zip;
 
 use crate::utils::data_format::YawListDataFormat;
+use crate::utils::{frame_times, simplify_path};
+
+use super::plane::Coordinate3D;
+use super::trajectory::Trajectory;
+
+//Maximum error, in degrees, of the simplified yaw generated by YawSetpointList.from_trajectory()
+const AUTO_YAW_TOLERANCE: f32 = 0.5;
+//Horizontal speed, in m/s, below which the travel direction is considered undefined
+const MIN_TRAVEL_SPEED: f32 = 0.1;
+//Horizontal distance, in meters, below which the direction to a target point is considered undefined
+const MIN_TARGET_DISTANCE: f32 = 0.1;
 
 /**
  * The simplest representation of a yaw setpoint
@@ -33,12 +44,57 @@ impl YawSetPoint {
 
 }
 
+/**
+ * Range in which yaw angles are returned
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum YawRange {
+    //Angles as stored in the setpoint list, which may grow beyond a full turn
+    UNWRAPPED,
+    //Angles in the range [0, 360)
+    POSITIVE,
+    //Angles in the range (-180, 180]
+    SIGNED,
+}
+
+impl YawRange {
+    /** Return the equivalent of an angle, in degrees, within this range */
+    pub fn normalize(&self, angle: f32) -> f32 {
+        //rem_euclid() may round tiny negative angles up to exactly 360
+        let positive = match angle.rem_euclid(360.0) {
+            wrapped if wrapped >= 360.0 => 0.0,
+            wrapped => wrapped
+        };
+
+        match self {
+            YawRange::UNWRAPPED => angle,
+            YawRange::POSITIVE => positive,
+            YawRange::SIGNED => if positive > 180.0 { positive - 360.0 } else { positive }
+        }
+    }
+}
+
+/**
+ * What the nose of a drone faces when its yaw is generated from its trajectory
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum YawTarget {
+    //The direction of the horizontal velocity
+    TRAVEL_DIRECTION,
+    //A fixed point, e.g. a camera or the center of the audience
+    POINT(Coordinate3D),
+    //A constant world heading, in degrees
+    HEADING(f32),
+}
+
 /**
  * Simplest representation of a causal yaw setpoint list in time.
  * 
  * SetPoints are assumed to be linear, i.e. yaw rate is constant between setpoints.
+ * Angles are unwrapped, so a change from 350 to 370 degrees turns 20 degrees clockwise
+ * while a change from 350 to 10 degrees turns 340 degrees counterclockwise.
  */
-struct YawSetpointList{
+pub struct YawSetpointList{
     setpoints: Vec<YawSetPoint>
 }
 
@@ -49,6 +105,112 @@ impl YawSetpointList {
         Self { setpoints: new_points }
     }
 
+    /**
+     * Generates the yaw of a drone from its trajectory.
+     *
+     * Headings are measured clockwise from the +Y axis, seen from above. When the target
+     * direction is undefined, e.g. while hovering or right above the target point, the last
+     * heading is kept. The generated angles are unwrapped.
+     *
+     * Parameters:
+     *     trajectory: the trajectory of the drone
+     *     target: what the nose of the drone faces
+     *     max_rate: maximum yaw rate in degrees per second; zero or less means no limit
+     *     fps: number of setpoints per second before simplification
+     */
+    pub fn from_trajectory(trajectory: &Trajectory, target: YawTarget, max_rate: f32, fps: f32) -> Self {
+        let (start, end) = match (trajectory.start_time(), trajectory.end_time()) {
+            (Some(start), Some(end)) if fps > 0.0 => (start, end),
+            _ => return Self::new(Vec::new())
+        };
+
+        let mut times = frame_times(start, end, fps);
+        if times.last().is_some_and(|last| *last < end) { times.push(end) }
+
+        let headings: Vec<Option<f32>> = times.iter().map(|t| target_heading(trajectory, target, *t, fps)).collect();
+        //Before the first defined heading, the drone already faces it
+        let mut angle = headings.iter().flatten().next().copied().unwrap_or(0.0);
+        let mut setpoints: Vec<YawSetPoint> = Vec::new();
+
+        for (index, (t, heading)) in zip(&times, &headings).enumerate() {
+            if let Some(heading) = heading {
+                let mut delta = YawRange::SIGNED.normalize(heading - angle);
+                if max_rate > 0.0 && index > 0 {
+                    let max_delta = max_rate * (t - times[index - 1]);
+                    delta = delta.clamp(-max_delta, max_delta);
+                }
+                angle += delta;
+            }
+            setpoints.push(YawSetPoint::new(*t, angle));
+        }
+
+        Self::new(setpoints).simplify_with(AUTO_YAW_TOLERANCE)
+    }
+
+    /** Return the setpoints of the list, sorted by time */
+    pub fn get_setpoints(&self) -> &[YawSetPoint] {
+        &self.setpoints
+    }
+
+    /**
+     * Evaluates the yaw angle at the given time, interpolating linearly between setpoints.
+     * The first and last angles are held before and after the list.
+     *
+     * Parameters:
+     *     t: the time in seconds
+     *     range: the range of the returned angle
+     *
+     * Returns:
+     *     the angle in degrees, or None if the list has no setpoints
+     */
+    pub fn angle_at(&self, t: f32, range: YawRange) -> Option<f32> {
+        let first = self.setpoints.first()?;
+        let last = self.setpoints.last()?;
+
+        let angle = if t <= first.get_time() {
+            first.get_angle()
+        } else if t >= last.get_time() {
+            last.get_angle()
+        } else {
+            //Index of the first setpoint strictly after t; it is always in [1, len - 1] here
+            let index = self.setpoints.partition_point(|setpoint| setpoint.get_time() <= t);
+            let (prev, next) = (self.setpoints[index - 1], self.setpoints[index]);
+            let ratio = (t - prev.get_time()) / (next.get_time() - prev.get_time());
+            prev.get_angle() + ratio * (next.get_angle() - prev.get_angle())
+        };
+
+        Some(range.normalize(angle))
+    }
+
+    /**
+     * Return the yaw rate at the given time, in degrees per second (positive clockwise).
+     * At a setpoint, the rate of the segment that starts there is returned. The rate is 0
+     * before the first setpoint and from the last one on.
+     */
+    pub fn rate_at(&self, t: f32) -> f32 {
+        let index = self.setpoints.partition_point(|setpoint| setpoint.get_time() <= t);
+        if index == 0 || index >= self.setpoints.len() { return 0.0 }
+
+        let (prev, next) = (self.setpoints[index - 1], self.setpoints[index]);
+        (next.get_angle() - prev.get_angle()) / (next.get_time() - prev.get_time())
+    }
+
+    /**
+     * Samples the yaw angle at a fixed frame rate.
+     *
+     * Parameters:
+     *     fps: the number of samples per second
+     *     start: the time of the first sample, in seconds
+     *     end: the time after which no more samples are taken, in seconds
+     *     range: the range of the sampled angles
+     */
+    pub fn sample(&self, fps: f32, start: f32, end: f32, range: YawRange) -> Vec<YawSetPoint> {
+        frame_times(start, end, fps)
+            .into_iter()
+            .filter_map(|t| self.angle_at(t, range).map(|angle| YawSetPoint::new(t, angle)))
+            .collect()
+    }
+
     /** Add a setpoint to the end of the setpoint list */
     pub fn append(&mut self, setpoint: YawSetPoint) {
         if self.setpoints.len() == 0 || self.setpoints.last().unwrap().get_time() >= setpoint.get_time() {
@@ -65,6 +227,14 @@ impl YawSetpointList {
         YawListDataFormat::new(&self.setpoints, 1)
     }
 
+    /**
+     * Create a setpoint list from its Skybrush-compatible representation, e.g. one parsed with
+     * YawListDataFormat::from_json().
+     */
+    pub fn from_skv_dict_format(data: &YawListDataFormat) -> Self {
+        Self::new(data.get_setpoints().to_vec())
+    }
+
     /**
      * Translate the yaw setpoints with the given delta angle. The setpoint list will be manipulated in-place
      */
@@ -75,82 +245,380 @@ impl YawSetpointList {
     }
 
     /**
-     * Simplify yaw setpoint list in list
-     * 
+     * Simplifies the setpoint list by removing setpoints whose angle can be interpolated
+     * from their neighbours, using the Ramer-Douglas-Peucker algorithm. Angles are compared
+     * as stored, so wrapped lists should be unwrapped first with unwrap_yaw().
+     *
+     * Parameters:
+     *     tolerance: the maximum angle difference allowed, in degrees
+     *
      * Returns:
-     *    The simplified yaw setpoint list
+     *     a new, simplified, yaw setpoint list
      */
-    pub fn simplify(&mut self) -> Vec<YawSetPoint>{
-        if self.setpoints.len() == 0 { return self.setpoints.clone() }
-
-        //set first yaw in the [0, 360) range and shift entire list accordingly
-        let angle = self.setpoints.first().unwrap().get_angle() % 360.0;
-        let delta = angle - self.setpoints.first().unwrap().get_angle();
-
-        if delta != 0.0 { self.shift(delta);}
-
-        //remove intermediate points on constant angular speed segments
-        let mut new_setpoints: Vec<YawSetPoint> = Vec::new();
-        let mut last_angular_speed = -1e12; //todo!() de donde sale este valor?
-
-        for index in 0..self.setpoints.len() {
-            let point = self.setpoints[index];
-            
-            if new_setpoints.len() == 0 {
-                new_setpoints.push(point);
-            } else {
-                let last = new_setpoints.last().unwrap();
-                let dt = point.get_time() - last.get_time();
-
-                if dt <= 0.0 {
-                    eprintln!("YawSetPointList.simplify: Yaw timestamp are not causal ({} <= {})", point.get_time(), last.get_time());
-                    continue;
-                }
+    pub fn simplify_with(&self, tolerance: f32) -> YawSetpointList {
+        YawSetpointList::new(simplify_path(self.setpoints.clone(), tolerance, yaw_distance_func))
+    }
 
-                //When calculating angular speed, we MUST round timestamps and angles to
-                //avoid large numeric errors at division by small numbers.
-                let angular_speed = (point.get_angle() - last.get_angle()) / dt;
-                if (angular_speed - last_angular_speed).abs() < 1e-6 {
-                    let last_index = new_setpoints.len() - 1;
-                    new_setpoints[last_index] = point;
-                } else {
-                    new_setpoints.push(point);
-                }
+    /**
+     * Unwraps the yaw angles of the setpoint list "in-place", so that consecutive angles never differ by more than
+     * the threshold when an equivalent angle exists. Each correction is a whole number of cycles and is carried over
+     * to all the following setpoints.
+     *
+     * Parameters:
+     *     threshold: the largest difference between consecutive angles left untouched, usually half a cycle
+     *     full_cycle: the size of a full turn in the unit of the angles, e.g. 360 for degrees
+     *
+     * Returns:
+     *     the unwrapped setpoints
+     */
+    pub fn unwrap_yaw(&mut self, threshold: f32, full_cycle: f32) -> Vec<YawSetPoint> {
+        if full_cycle <= 0.0 {
+            eprintln!("YawSetPointList.unwrap_yaw: The full cycle must be positive");
+            return self.setpoints.clone();
+        }
+
+        let mut offset = 0.0;
+        let mut previous: Option<f32> = None;
 
-                last_angular_speed = angular_speed;
+        for setpoint in self.setpoints.iter_mut() {
+            let angle = setpoint.get_angle();
+            if let Some(previous) = previous {
+                let diff = angle - previous;
+                if diff.abs() > threshold {
+                    offset -= (diff / full_cycle).round() * full_cycle;
+                }
             }
-        }
 
-        self.setpoints = new_setpoints;
+            previous = Some(angle);
+            setpoint.change_angle(offset);
+        }
 
         self.setpoints.clone()
     }
+}
 
-    /**
-     * Unwraps the yaw angles of the setpoint list "in-place" and ensures that consecutives sampled angles never
-     * have a difference of more than 180 degrees.
-     */
-    pub fn unwrap_yaw(&mut self, threshold: f32, full_cycle: f32) -> Vec<YawSetPoint> {
-        let threshold = 180.0;
-        let full_cycle = 180.0;
-
-        //We use -2 because len() give us the length of the vector and if we want to iter over it
-        //we have to put the limit in length -1, and how we are going to take the index element and
-        //the next one in each iteration, -2 is necesarry in order of not create an outbound error
-        for index in 0..self.setpoints.len() - 2 {
-            let prev = self.setpoints[index];
-            let mut curr = self.setpoints[index + 1]; //The reaseon of the previous -2
-
-            let diff = curr.get_angle() - prev.get_angle();
-            
-            if diff > threshold || diff < -threshold {
-                let num_cycles = -(diff / full_cycle);
-                curr.change_angle(num_cycles * full_cycle);
-                self.setpoints[index + 1 ] = curr;
-            }
+
+/* Distance function for YawSetpointList.simplify_with() */
+fn yaw_distance_func(setpoints: &Vec<YawSetPoint>, start: &YawSetPoint, end: &YawSetPoint) -> Vec<f32> {
+    let timespan = end.get_time() - start.get_time();
+
+    setpoints.iter()
+        .map(|setpoint| {
+            let ratio = if timespan > 0.0 { (setpoint.get_time() - start.get_time()) / timespan } else { 0.0 };
+            let interp = start.get_angle() + ratio * (end.get_angle() - start.get_angle());
+            (setpoint.get_angle() - interp).abs()
+        })
+        .collect()
+}
+
+/* Heading that the target asks for at the given time, if defined */
+fn target_heading(trajectory: &Trajectory, target: YawTarget, t: f32, fps: f32) -> Option<f32> {
+    let heading = |dx: f32, dy: f32| dx.atan2(dy).to_degrees();
+
+    match target {
+        YawTarget::HEADING(angle) => Some(angle),
+        YawTarget::POINT(point) => {
+            let position = trajectory.position_at(t)?;
+            let (dx, dy) = (point[0] - position.get_x(), point[1] - position.get_y());
+            if dx.hypot(dy) < MIN_TARGET_DISTANCE { return None }
+
+            Some(heading(dx, dy))
+        },
+        YawTarget::TRAVEL_DIRECTION => {
+            let step = 0.5 / fps;
+            let (before, after) = (trajectory.position_at(t - step)?, trajectory.position_at(t + step)?);
+            let (dx, dy) = (after.get_x() - before.get_x(), after.get_y() - before.get_y());
+            if dx.hypot(dy) < MIN_TRAVEL_SPEED * 2.0 * step { return None }
+
+            Some(heading(dx, dy))
         }
-        
-        self.setpoints.clone()
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::point::Point4D;
+
+    fn list(angles: &[f32]) -> YawSetpointList {
+        YawSetpointList::new(angles.iter().enumerate().map(|(index, angle)| YawSetPoint::new(index as f32, *angle)).collect())
+    }
+
+    fn angles(setpoints: &[YawSetPoint]) -> Vec<f32> {
+        setpoints.iter().map(|setpoint| setpoint.get_angle()).collect()
+    }
+
+    fn assert_angles(actual: &[YawSetPoint], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in zip(angles(actual), expected) {
+            assert!((actual - expected).abs() < 1e-4, "{} != {}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn unwrap_empty_list() {
+        assert!(list(&[]).unwrap_yaw(180.0, 360.0).is_empty());
+    }
+
+    #[test]
+    fn unwrap_single_setpoint() {
+        assert_angles(&list(&[370.0]).unwrap_yaw(180.0, 360.0), &[370.0]);
+    }
+
+    #[test]
+    fn unwrap_keeps_small_changes() {
+        assert_angles(&list(&[0.0, 90.0, 180.0, 10.0]).unwrap_yaw(180.0, 360.0), &[0.0, 90.0, 180.0, 10.0]);
+    }
+
+    #[test]
+    fn unwrap_last_setpoint() {
+        assert_angles(&list(&[350.0, 10.0]).unwrap_yaw(180.0, 360.0), &[350.0, 370.0]);
+    }
+
+    #[test]
+    fn unwrap_counterclockwise() {
+        assert_angles(&list(&[10.0, 350.0, 330.0]).unwrap_yaw(180.0, 360.0), &[10.0, -10.0, -30.0]);
+    }
+
+    #[test]
+    fn unwrap_compounds_corrections() {
+        assert_angles(
+            &list(&[0.0, 170.0, 340.0, 150.0, 320.0, 130.0]).unwrap_yaw(180.0, 360.0),
+            &[0.0, 170.0, 340.0, 510.0, 680.0, 850.0]
+        );
+    }
+
+    #[test]
+    fn unwrap_jumps_of_several_cycles() {
+        assert_angles(&list(&[0.0, 730.0, -700.0]).unwrap_yaw(180.0, 360.0), &[0.0, 10.0, 20.0]);
+    }
+
+    #[test]
+    fn unwrap_honors_threshold() {
+        assert_angles(&list(&[0.0, 200.0]).unwrap_yaw(270.0, 360.0), &[0.0, 200.0]);
+        assert_angles(&list(&[0.0, 300.0]).unwrap_yaw(270.0, 360.0), &[0.0, -60.0]);
+    }
+
+    #[test]
+    fn unwrap_honors_full_cycle() {
+        let pi = std::f32::consts::PI;
+        assert_angles(&list(&[3.0, -3.0]).unwrap_yaw(pi, 2.0 * pi), &[3.0, 2.0 * pi - 3.0]);
+    }
+
+    #[test]
+    fn unwrap_updates_list_in_place() {
+        let mut setpoints = list(&[350.0, 10.0, 30.0]);
+        setpoints.unwrap_yaw(180.0, 360.0);
+        assert_angles(setpoints.get_setpoints(), &[350.0, 370.0, 390.0]);
+        assert_angles(&setpoints.unwrap_yaw(180.0, 360.0), &[350.0, 370.0, 390.0]);
+    }
+
+    #[test]
+    fn unwrap_rejects_invalid_cycle() {
+        assert_angles(&list(&[350.0, 10.0]).unwrap_yaw(180.0, 0.0), &[350.0, 10.0]);
+    }
+
+    #[test]
+    fn normalize_ranges() {
+        let cases = [
+            (0.0, 0.0, 0.0),
+            (180.0, 180.0, 180.0),
+            (-180.0, 180.0, 180.0),
+            (360.0, 0.0, 0.0),
+            (-360.0, 0.0, 0.0),
+            (540.0, 180.0, 180.0),
+            (190.0, 190.0, -170.0),
+            (-90.0, 270.0, -90.0),
+            (725.0, 5.0, 5.0),
+            (-1e-6, 0.0, 0.0),
+        ];
+
+        for (angle, positive, signed) in cases {
+            assert_eq!(YawRange::UNWRAPPED.normalize(angle), angle);
+            assert_eq!(YawRange::POSITIVE.normalize(angle), positive, "{} in [0, 360)", angle);
+            assert_eq!(YawRange::SIGNED.normalize(angle), signed, "{} in (-180, 180]", angle);
+        }
+    }
+
+    #[test]
+    fn angle_of_empty_list() {
+        assert_eq!(list(&[]).angle_at(0.0, YawRange::UNWRAPPED), None);
+        assert_eq!(list(&[]).rate_at(0.0), 0.0);
+        assert!(list(&[]).sample(10.0, 0.0, 1.0, YawRange::UNWRAPPED).is_empty());
+    }
+
+    #[test]
+    fn angle_is_interpolated_between_setpoints() {
+        let setpoints = list(&[350.0, 370.0, 10.0]);
+
+        assert_eq!(setpoints.angle_at(0.0, YawRange::UNWRAPPED), Some(350.0));
+        assert_eq!(setpoints.angle_at(0.25, YawRange::UNWRAPPED), Some(355.0));
+        assert_eq!(setpoints.angle_at(1.0, YawRange::UNWRAPPED), Some(370.0));
+        assert_eq!(setpoints.angle_at(1.5, YawRange::UNWRAPPED), Some(190.0));
+        //The unwrapped angle is interpolated first, so the turn keeps its direction
+        assert_eq!(setpoints.angle_at(0.75, YawRange::POSITIVE), Some(5.0));
+        assert_eq!(setpoints.angle_at(0.25, YawRange::SIGNED), Some(-5.0));
+        assert_eq!(setpoints.angle_at(1.5, YawRange::SIGNED), Some(-170.0));
+    }
+
+    #[test]
+    fn angle_is_held_outside_the_list() {
+        let setpoints = list(&[370.0, 400.0]);
+
+        assert_eq!(setpoints.angle_at(-5.0, YawRange::UNWRAPPED), Some(370.0));
+        assert_eq!(setpoints.angle_at(-5.0, YawRange::POSITIVE), Some(10.0));
+        assert_eq!(setpoints.angle_at(5.0, YawRange::UNWRAPPED), Some(400.0));
+        assert_eq!(list(&[90.0]).angle_at(3.0, YawRange::SIGNED), Some(90.0));
+    }
+
+    #[test]
+    fn rate_between_setpoints() {
+        let setpoints = list(&[0.0, 90.0, 60.0]);
+
+        assert_eq!(setpoints.rate_at(-1.0), 0.0);
+        assert_eq!(setpoints.rate_at(0.0), 90.0);
+        assert_eq!(setpoints.rate_at(0.5), 90.0);
+        //At a setpoint, the rate of the following segment
+        assert_eq!(setpoints.rate_at(1.0), -30.0);
+        assert_eq!(setpoints.rate_at(2.0), 0.0);
+        assert_eq!(setpoints.rate_at(3.0), 0.0);
+    }
+
+    #[test]
+    fn sample_at_frame_rate() {
+        let samples = list(&[340.0, 380.0]).sample(4.0, -0.25, 1.1, YawRange::POSITIVE);
+        let times: Vec<f32> = samples.iter().map(|sample| sample.get_time()).collect();
+
+        assert_eq!(times, vec![-0.25, 0.0, 0.25, 0.5, 0.75, 1.0]);
+        assert_angles(&samples, &[340.0, 340.0, 350.0, 0.0, 10.0, 20.0]);
+        assert!(list(&[0.0, 10.0]).sample(0.0, 0.0, 1.0, YawRange::UNWRAPPED).is_empty());
+    }
+
+    /* Straight flight from (x0, y0) at 0 s to (x1, y1) at the given time, at 10 m high */
+    fn line(from: (f32, f32), to: (f32, f32), duration: f32) -> Trajectory {
+        Trajectory::new(vec![Point4D::new(0.0, from.0, from.1, 10.0), Point4D::new(duration, to.0, to.1, 10.0)])
+    }
+
+    fn assert_yaw(setpoints: &YawSetpointList, t: f32, expected: f32) {
+        let angle = setpoints.angle_at(t, YawRange::UNWRAPPED).unwrap();
+        assert!((angle - expected).abs() <= AUTO_YAW_TOLERANCE + 1e-3, "{} != {} at {} s", angle, expected, t);
+    }
+
+    #[test]
+    fn auto_yaw_of_empty_trajectory() {
+        assert!(YawSetpointList::from_trajectory(&Trajectory::new(Vec::new()), YawTarget::HEADING(0.0), 0.0, 10.0).get_setpoints().is_empty());
+        assert!(YawSetpointList::from_trajectory(&line((0.0, 0.0), (1.0, 0.0), 1.0), YawTarget::HEADING(0.0), 0.0, 0.0).get_setpoints().is_empty());
+    }
+
+    #[test]
+    fn auto_yaw_faces_travel_direction() {
+        //Headings are clockwise from +Y, so +X is 90 degrees and -Y is 180 degrees
+        let east = YawSetpointList::from_trajectory(&line((0.0, 0.0), (10.0, 0.0), 10.0), YawTarget::TRAVEL_DIRECTION, 0.0, 10.0);
+        assert_angles(east.get_setpoints(), &[90.0, 90.0]);
+        assert_eq!(east.get_setpoints()[1].get_time(), 10.0);
+
+        let south = YawSetpointList::from_trajectory(&line((0.0, 0.0), (0.0, -10.0), 2.0), YawTarget::TRAVEL_DIRECTION, 0.0, 10.0);
+        assert_angles(south.get_setpoints(), &[180.0, 180.0]);
+    }
+
+    #[test]
+    fn auto_yaw_keeps_heading_while_hovering() {
+        let trajectory = Trajectory::new(vec![
+            Point4D::new(0.0, 0.0, 0.0, 10.0),
+            Point4D::new(2.0, 0.0, 0.0, 10.0),
+            Point4D::new(4.0, -10.0, 0.0, 10.0),
+            Point4D::new(6.0, -10.0, 0.0, 10.0),
+        ]);
+        let setpoints = YawSetpointList::from_trajectory(&trajectory, YawTarget::TRAVEL_DIRECTION, 0.0, 10.0);
+
+        for t in [0.0, 1.0, 3.0, 5.0, 6.0] {
+            assert_yaw(&setpoints, t, -90.0);
+        }
+    }
+
+    #[test]
+    fn auto_yaw_faces_point() {
+        let trajectory = line((-10.0, -10.0), (10.0, -10.0), 10.0);
+        let setpoints = YawSetpointList::from_trajectory(&trajectory, YawTarget::POINT([0.0, 0.0, 1.5]), 0.0, 10.0);
+
+        assert_yaw(&setpoints, 0.0, 45.0);
+        assert_yaw(&setpoints, 2.5, 26.565);
+        assert_yaw(&setpoints, 5.0, 0.0);
+        assert_yaw(&setpoints, 10.0, -45.0);
+        assert!(setpoints.get_setpoints().len() < 101);
+    }
+
+    #[test]
+    fn auto_yaw_holds_heading() {
+        let trajectory = line((0.0, 0.0), (5.0, 5.0), 3.0);
+
+        let setpoints = YawSetpointList::from_trajectory(&trajectory, YawTarget::HEADING(-135.0), 30.0, 10.0);
+        assert_angles(setpoints.get_setpoints(), &[-135.0, -135.0]);
+
+        //Wrapped headings are reached through the shortest turn
+        let setpoints = YawSetpointList::from_trajectory(&trajectory, YawTarget::HEADING(270.0), 30.0, 10.0);
+        assert_angles(setpoints.get_setpoints(), &[270.0, 270.0]);
+    }
+
+    #[test]
+    fn auto_yaw_turns_through_the_shortest_way() {
+        //Flying past the point turns the nose from 95.7 through 180 to 264.3 degrees
+        let trajectory = line((-10.0, 1.0), (10.0, 1.0), 2.0);
+        let setpoints = YawSetpointList::from_trajectory(&trajectory, YawTarget::POINT([0.0, 0.0, 0.0]), 0.0, 50.0);
+
+        assert_yaw(&setpoints, 0.0, 95.711);
+        assert_yaw(&setpoints, 1.0, 180.0);
+        assert_yaw(&setpoints, 2.0, 264.289);
+        assert!(setpoints.rate_at(1.0) > 90.0);
+    }
+
+    #[test]
+    fn auto_yaw_honors_max_rate() {
+        let trajectory = line((-10.0, 1.0), (10.0, 1.0), 2.0);
+        let setpoints = YawSetpointList::from_trajectory(&trajectory, YawTarget::POINT([0.0, 0.0, 0.0]), 90.0, 50.0);
+        let points = setpoints.get_setpoints();
+
+        for (prev, next) in zip(points, &points[1..]) {
+            let rate = (next.get_angle() - prev.get_angle()) / (next.get_time() - prev.get_time());
+            assert!(rate.abs() <= 90.0 + 1e-2, "{} deg/s from {} s", rate, prev.get_time());
+        }
+        //The turn lags behind the target, but never overshoots it
+        let last = setpoints.angle_at(2.0, YawRange::UNWRAPPED).unwrap();
+        assert!(last < 264.0 && last > 180.0, "{}", last);
+    }
+
+    #[test]
+    fn simplify_collapses_noisy_ramp() {
+        //A 10 deg/s ramp with +-0.2 degrees of noise
+        let noisy: Vec<f32> = (0..50).map(|index| index as f32 * 10.0 + if index % 2 == 0 { 0.2 } else { -0.2 }).collect();
+        let simplified = list(&noisy).simplify_with(0.5);
+
+        assert_angles(simplified.get_setpoints(), &[0.2, 489.8]);
+        assert_eq!(simplified.get_setpoints()[1].get_time(), 49.0);
+    }
+
+    #[test]
+    fn simplify_honors_tolerance() {
+        //The middle setpoint is 1 degree away from the line between its neighbours
+        assert_angles(list(&[0.0, 11.0, 20.0]).simplify_with(1.0).get_setpoints(), &[0.0, 20.0]);
+        assert_angles(list(&[0.0, 11.0, 20.0]).simplify_with(0.99).get_setpoints(), &[0.0, 11.0, 20.0]);
+        assert_angles(list(&[0.0, 0.0, 5.0, 0.0, 0.0]).simplify_with(4.0).get_setpoints(), &[0.0, 5.0, 0.0]);
+    }
+
+    #[test]
+    fn simplify_returns_a_new_list() {
+        let setpoints = list(&[0.0, 10.0, 20.0, 30.0]);
+        let simplified = setpoints.simplify_with(0.1);
+
+        assert_angles(simplified.get_setpoints(), &[0.0, 30.0]);
+        assert_angles(setpoints.get_setpoints(), &[0.0, 10.0, 20.0, 30.0]);
+        assert!(list(&[]).simplify_with(0.1).get_setpoints().is_empty());
+        assert_angles(list(&[5.0]).simplify_with(0.1).get_setpoints(), &[5.0]);
+    }
+
+    #[test]
+    fn simplify_keeps_unwrapped_turns() {
+        //Angles are compared as stored, so a full turn is not mistaken for no turn
+        assert_angles(list(&[0.0, 180.0, 360.0, 360.0]).simplify_with(1.0).get_setpoints(), &[0.0, 360.0, 360.0]);
+    }
+}
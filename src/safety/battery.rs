@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use crate::model::light_program::LightProgram;
+use crate::model::safety_check::SafetyCheckResult;
+use crate::model::trajectory::Trajectory;
+
+use super::checker::{position_of, GROUND_ALTITUDE};
+
+const SECONDS_PER_HOUR: f32 = 3600.0;
+
+//Number of evaluations of the light program between consecutive keypoints when averaging its brightness
+const BRIGHTNESS_SAMPLES: usize = 16;
+
+/**
+ * Battery and power consumption parameters of a drone model
+ */
+#[derive(Clone, Copy)]
+pub struct BatteryParams {
+    //Usable battery capacity in watt-hours
+    pub capacity: f32,
+    //Fraction of the capacity that must remain when landing, in the range [0-1]
+    pub reserve: f32,
+    //Power needed to hover, in watts
+    pub hover_power: f32,
+    //Extra power per m/s of horizontal speed, in watts
+    pub horizontal_power: f32,
+    //Extra power per m/s of climb rate, in watts
+    pub climb_power: f32,
+    //Extra power per m/s of descent rate, in watts
+    pub descent_power: f32,
+    //Power drawn by the LEDs at full white, in watts
+    pub max_led_power: f32,
+}
+
+impl BatteryParams {
+    pub fn new() -> Self {
+        Self {
+            capacity: 20.0,
+            reserve: 0.2,
+            hover_power: 70.0,
+            horizontal_power: 4.0,
+            climb_power: 25.0,
+            descent_power: 5.0,
+            max_led_power: 3.0,
+        }
+    }
+
+    pub fn new_from(
+        capacity: f32,
+        reserve: f32,
+        hover_power: f32,
+        horizontal_power: f32,
+        climb_power: f32,
+        descent_power: f32,
+        max_led_power: f32,
+    ) -> Self {
+        Self {
+            capacity,
+            reserve,
+            hover_power,
+            horizontal_power,
+            climb_power,
+            descent_power,
+            max_led_power,
+        }
+    }
+
+    pub fn as_dict(&self) -> HashMap<&'static str, f32> {
+        HashMap::from([
+            ("capacity", self.capacity),
+            ("reserve", self.reserve),
+            ("hoverPower", self.hover_power),
+            ("horizontalPower", self.horizontal_power),
+            ("climbPower", self.climb_power),
+            ("descentPower", self.descent_power),
+            ("maxLedPower", self.max_led_power),
+        ])
+    }
+}
+
+/**
+ * Predicted energy usage of a single drone during the show, in watt-hours
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EnergyEstimate {
+    //Energy spent flying (hovering, moving and climbing)
+    pub flight: f32,
+    //Energy spent on the LEDs while airborne
+    pub led: f32,
+    //Energy left in the battery when landing
+    pub remaining: f32,
+    //Whether the remaining energy is below the reserve margin
+    pub is_low: bool,
+}
+
+impl EnergyEstimate {
+    pub fn total(&self) -> f32 {
+        self.flight + self.led
+    }
+}
+
+/**
+ * Estimates the energy each drone spends during the show and flags in the given result the
+ * drones predicted to land with less charge than the reserve margin.
+ *
+ * Parameters:
+ *     trajectories: the trajectory of each drone of the show
+ *     light_programs: the light program of each drone of the show; drones without one are
+ *                     assumed to keep their LEDs off
+ *     params: the battery parameters of the drones
+ *     result: the safety check result where the landing position of low drones is added
+ *
+ * Returns:
+ *     the estimate of each drone, in the same order as the trajectories
+ */
+pub fn check_battery(trajectories: &[Trajectory], light_programs: &[LightProgram], params: &BatteryParams, result: &mut SafetyCheckResult) -> Vec<EnergyEstimate> {
+    let estimates: Vec<EnergyEstimate> = trajectories.iter()
+        .enumerate()
+        .map(|(drone, trajectory)| estimate_energy(trajectory, light_programs.get(drone), params))
+        .collect();
+
+    for (trajectory, estimate) in trajectories.iter().zip(&estimates) {
+        if let (true, Some(end)) = (estimate.is_low, trajectory.end_time()) {
+            result.drones_low_battery.push(position_of(trajectory, end));
+        }
+    }
+
+    estimates
+}
+
+/**
+ * Estimates the energy a drone spends flying its trajectory and showing its light program.
+ *
+ * Flight power is the hover power plus terms proportional to the horizontal speed and the
+ * climb or descent rate of each trajectory segment. LED power is proportional to the mean
+ * of the RGB channels of the colors shown, following the easing and color space of the
+ * fades, and is only counted while the drone is airborne.
+ */
+pub fn estimate_energy(trajectory: &Trajectory, light_program: Option<&LightProgram>, params: &BatteryParams) -> EnergyEstimate {
+    let mut flight = 0.0;
+    let mut led = 0.0;
+
+    for segment in trajectory.get_points().windows(2) {
+        let (start, end) = (segment[0], segment[1]);
+        let dt = end.get_t() - start.get_t();
+        if dt <= 0.0 || (start.get_z() <= GROUND_ALTITUDE && end.get_z() <= GROUND_ALTITUDE) {
+            continue;
+        }
+
+        let dx = end.get_x() - start.get_x();
+        let dy = end.get_y() - start.get_y();
+        let dz = end.get_z() - start.get_z();
+        let joules = params.hover_power * dt
+            + params.horizontal_power * (dx * dx + dy * dy).sqrt()
+            + params.climb_power * dz.max(0.0)
+            + params.descent_power * (-dz).max(0.0);
+        flight += joules / SECONDS_PER_HOUR;
+
+        if let Some(light_program) = light_program {
+            led += params.max_led_power * mean_brightness(light_program, start.get_t(), end.get_t()) * dt / SECONDS_PER_HOUR;
+        }
+    }
+
+    let remaining = params.capacity - flight - led;
+    EnergyEstimate { flight, led, remaining, is_low: remaining < params.reserve * params.capacity }
+}
+
+/**
+ * Returns the mean brightness, in the range [0-1], of a light program between two times.
+ * The program is evaluated at the middle of equal parts of the time between consecutive
+ * keypoints, so held colors are exact and fades are shown as the drone shows them.
+ */
+fn mean_brightness(light_program: &LightProgram, start: f32, end: f32) -> f32 {
+    if end <= start { return 0.0 }
+
+    let brightness = |t: f32| light_program.array_at(t).map_or(0.0, |color| (color[0] + color[1] + color[2]) / 3.0);
+    let mut bounds: Vec<f32> = vec![start];
+    bounds.extend(light_program.get_colors().iter().map(|color| color.t).filter(|t| *t > start && *t < end));
+    bounds.push(end);
+
+    let integral: f32 = bounds.windows(2)
+        .map(|span| {
+            let width = (span[1] - span[0]) / BRIGHTNESS_SAMPLES as f32;
+            (0..BRIGHTNESS_SAMPLES).map(|index| brightness(span[0] + (index as f32 + 0.5) * width) * width).sum::<f32>()
+        })
+        .sum();
+
+    integral / (end - start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::colors::ColorSpace;
+    use crate::model::color::{Color3D, Color4D};
+    use crate::model::easing::Easing;
+    use crate::model::point::Point4D;
+
+    fn white(t: f32, is_fade: bool) -> Color4D {
+        Color3D::new(255, 255, 255).at_time(t, is_fade)
+    }
+
+    fn black(t: f32, is_fade: bool) -> Color4D {
+        Color3D::new(0, 0, 0).at_time(t, is_fade)
+    }
+
+    /* Hovers at 10 meters for 100 seconds */
+    fn hover() -> Trajectory {
+        Trajectory::new(vec![Point4D::new(0.0, 0.0, 0.0, 10.0), Point4D::new(100.0, 0.0, 0.0, 10.0)])
+    }
+
+    fn led_energy(colors: Vec<Color4D>) -> f32 {
+        estimate_energy(&hover(), Some(&LightProgram::new(colors)), &BatteryParams::new()).led
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() <= 1e-3 * expected.abs().max(1e-3), "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn hover_flight_energy() {
+        let estimate = estimate_energy(&hover(), None, &BatteryParams::new());
+
+        assert_close(estimate.flight, 70.0 * 100.0 / SECONDS_PER_HOUR);
+        assert_eq!(estimate.led, 0.0);
+        assert_close(estimate.remaining, 20.0 - estimate.flight);
+        assert!(!estimate.is_low);
+    }
+
+    #[test]
+    fn held_colors() {
+        //Full white during the first half, held from before the show starts
+        let energy = led_energy(vec![white(20.0, false), black(50.0, false)]);
+        assert_close(energy, 3.0 * 0.5 * 100.0 / SECONDS_PER_HOUR);
+    }
+
+    #[test]
+    fn linear_fade() {
+        let energy = led_energy(vec![black(0.0, false), white(100.0, true)]);
+        assert_close(energy, 3.0 * 0.5 * 100.0 / SECONDS_PER_HOUR);
+    }
+
+    #[test]
+    fn eased_fade() {
+        let samples = 10000;
+        let mean = (0..samples).map(|index| Easing::EASE_IN.apply((index as f32 + 0.5) / samples as f32)).sum::<f32>() / samples as f32;
+        let energy = led_energy(vec![black(0.0, false), white(100.0, true).with_easing(Easing::EASE_IN)]);
+
+        assert!(mean < 0.4);
+        assert!((energy - 3.0 * mean * 100.0 / SECONDS_PER_HOUR).abs() < 1e-3 * energy);
+    }
+
+    #[test]
+    fn fade_in_color_space_of_the_program() {
+        //Red to blue is dimmer in RGB than through magenta in HSV
+        let colors = vec![Color3D::new(255, 0, 0).at_time(0.0, false), Color3D::new(0, 0, 255).at_time(100.0, true)];
+        let mut program = LightProgram::new(colors);
+        let rgb = estimate_energy(&hover(), Some(&program), &BatteryParams::new()).led;
+        program.set_color_space(ColorSpace::HSV);
+        let hsv = estimate_energy(&hover(), Some(&program), &BatteryParams::new()).led;
+
+        assert_close(rgb, 3.0 / 3.0 * 100.0 / SECONDS_PER_HOUR);
+        assert!(hsv > 1.2 * rgb);
+    }
+
+    #[test]
+    fn leds_only_count_while_airborne() {
+        let trajectory = Trajectory::new(vec![
+            Point4D::new(0.0, 0.0, 0.0, 0.0),
+            Point4D::new(50.0, 0.0, 0.0, 0.0),
+            Point4D::new(100.0, 0.0, 0.0, 0.05),
+        ]);
+        let program = LightProgram::new(vec![white(0.0, false)]);
+
+        assert_eq!(estimate_energy(&trajectory, Some(&program), &BatteryParams::new()).total(), 0.0);
+    }
+
+    #[test]
+    fn low_battery_is_flagged() {
+        //The hover alone leaves more than the reserve of 2.5 Wh, but not with the LEDs at full white
+        let mut params = BatteryParams::new();
+        params.capacity = 2.5;
+        let white = LightProgram::new(vec![white(0.0, false)]);
+        let dark = LightProgram::new(vec![black(0.0, false)]);
+
+        assert!(!estimate_energy(&hover(), Some(&dark), &params).is_low);
+        assert!(estimate_energy(&hover(), Some(&white), &params).is_low);
+
+        let mut result = SafetyCheckResult::new_empty();
+        let estimates = check_battery(&[hover(), hover()], &[dark, white], &params, &mut result);
+        assert_eq!(estimates.iter().map(|estimate| estimate.is_low).collect::<Vec<_>>(), vec![false, true]);
+        assert_eq!(result.drones_low_battery, vec![[0.0, 0.0, 10.0]]);
+    }
+}
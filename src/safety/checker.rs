@@ -0,0 +1,611 @@
+use std::cmp::Ordering;
+use std::thread;
+
+use crate::model::plane::Coordinate3D;
+use crate::model::safety_check::{SafetyCheckParams, SafetyCheckResult};
+use crate::model::trajectory::Trajectory;
+use crate::utils::frame_count;
+
+//Drones at or below this altitude, in meters, are considered to be on the ground
+pub const GROUND_ALTITUDE: f32 = 0.1;
+
+/**
+ * Outcome of the safety check of a single frame of the show.
+ *
+ * Drones are referenced by their index in the list of trajectories that was checked, so
+ * the outcome can be turned into a SafetyCheckResult later on or patched when only some
+ * of the drones change.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct FrameCheck {
+    //Time of the frame in seconds
+    pub time: f32,
+    pub over_max_altitude: Vec<usize>,
+    pub over_max_velocity_xy: Vec<usize>,
+    pub over_max_velocity_z: Vec<usize>,
+    pub below_min_nav_altitude: Vec<usize>,
+    pub outside_vertical_corridor: Vec<usize>,
+    //Pairs of drones closer than the minimum distance, as (lower index, higher index)
+    pub close_pairs: Vec<(usize, usize)>,
+    pub closest_pair: Option<(usize, usize)>,
+    pub min_distance: Option<f32>,
+    pub min_altitude: Option<f32>,
+}
+
+impl FrameCheck {
+    /** Creates the outcome of a frame without any violation */
+    pub fn new(time: f32) -> Self {
+        Self {
+            time,
+            over_max_altitude: Vec::new(),
+            over_max_velocity_xy: Vec::new(),
+            over_max_velocity_z: Vec::new(),
+            below_min_nav_altitude: Vec::new(),
+            outside_vertical_corridor: Vec::new(),
+            close_pairs: Vec::new(),
+            closest_pair: None,
+            min_distance: None,
+            min_altitude: None,
+        }
+    }
+
+    /**
+     * Stores the single drone checks of a drone, replacing any previous outcome for it.
+     * Drone lists are kept sorted by index.
+     */
+    pub fn set_drone_flags(&mut self, drone: usize, flags: DroneFlags) {
+        set_membership(&mut self.over_max_altitude, drone, flags.over_max_altitude);
+        set_membership(&mut self.over_max_velocity_xy, drone, flags.over_max_velocity_xy);
+        set_membership(&mut self.over_max_velocity_z, drone, flags.over_max_velocity_z);
+        set_membership(&mut self.below_min_nav_altitude, drone, flags.below_min_nav_altitude);
+        set_membership(&mut self.outside_vertical_corridor, drone, flags.outside_vertical_corridor);
+    }
+
+    /**
+     * Convert the frame outcome into a SafetyCheckResult holding the coordinates of the
+     * drones involved at the time of the frame.
+     */
+    pub fn as_result(&self, trajectories: &[Trajectory]) -> SafetyCheckResult {
+        let coordinate = |index: usize| position_of(&trajectories[index], self.time);
+        let coordinates = |indices: &Vec<usize>| indices.iter().map(|index| coordinate(*index)).collect();
+        let pair = |pair: &(usize, usize)| (coordinate(pair.0), coordinate(pair.1));
+
+        let mut result = SafetyCheckResult::new_full_from(
+            coordinates(&self.over_max_altitude),
+            coordinates(&self.over_max_velocity_xy),
+            coordinates(&self.over_max_velocity_z),
+            coordinates(&self.below_min_nav_altitude),
+            self.closest_pair.iter().map(pair).collect(),
+            self.min_distance.into_iter().collect(),
+            self.min_altitude.into_iter().collect(),
+            self.close_pairs.iter().map(pair).collect()
+        );
+        result.drones_outside_vertical_corridor = coordinates(&self.outside_vertical_corridor);
+
+        result
+    }
+}
+
+/**
+ * Outcome of the checks that involve a single drone at a single frame
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct DroneFlags {
+    pub over_max_altitude: bool,
+    pub over_max_velocity_xy: bool,
+    pub over_max_velocity_z: bool,
+    pub below_min_nav_altitude: bool,
+    pub outside_vertical_corridor: bool,
+}
+
+impl DroneFlags {
+    pub fn has_violation(&self) -> bool {
+        self.over_max_altitude
+            || self.over_max_velocity_xy
+            || self.over_max_velocity_z
+            || self.below_min_nav_altitude
+            || self.outside_vertical_corridor
+    }
+}
+
+/** Adds or removes an index from a sorted list of indices */
+fn set_membership(indices: &mut Vec<usize>, index: usize, is_member: bool) {
+    match indices.binary_search(&index) {
+        Ok(position) => if !is_member { indices.remove(position); },
+        Err(position) => if is_member { indices.insert(position, index); }
+    }
+}
+
+/**
+ * Runs the safety checks of a whole show, sampling the trajectories of the drones at a
+ * fixed frame rate.
+ *
+ * The result of a show check is the concatenation of the results of every frame, in frame
+ * order. closest_pair, min_distance and min_altitude hold one entry per frame, except for
+ * frames where they are undefined (e.g. closest pair with a single drone).
+ */
+pub struct SafetyChecker {
+    params: SafetyCheckParams,
+    //Number of frames per second used to sample the trajectories
+    fps: f32,
+}
+
+impl SafetyChecker {
+    pub fn new(params: SafetyCheckParams, fps: f32) -> Self {
+        Self { params, fps }
+    }
+
+    pub fn get_params(&self) -> &SafetyCheckParams {
+        &self.params
+    }
+
+    pub fn get_fps(&self) -> f32 {
+        self.fps
+    }
+
+    /**
+     * Checks the whole show on the current thread.
+     *
+     * Parameters:
+     *     trajectories: the trajectory of each drone of the show
+     *
+     * Returns:
+     *     the result of the check, or an error if the input can not be checked
+     */
+    pub fn check(&self, trajectories: &[Trajectory]) -> Result<SafetyCheckResult, &'static str> {
+        let context = self.context(trajectories)?;
+        let frames = self.check_frame_range(&context, 0, context.num_frames);
+
+        Ok(merge_frame_checks(&frames, trajectories))
+    }
+
+    /**
+     * Checks the whole show splitting the frames in contiguous chunks across several threads.
+     *
+     * The outcome of every chunk is merged in frame order, so the result is identical to
+     * the one returned by check().
+     *
+     * Parameters:
+     *     trajectories: the trajectory of each drone of the show
+     *     num_threads: number of worker threads; 0 uses the available parallelism of the host
+     */
+    pub fn check_parallel(&self, trajectories: &[Trajectory], num_threads: usize) -> Result<SafetyCheckResult, &'static str> {
+        let frames = self.check_frames_parallel(trajectories, num_threads)?;
+
+        Ok(merge_frame_checks(&frames, trajectories))
+    }
+
+    /**
+     * Checks every frame of the show across several threads and returns the outcome of
+     * each frame, in frame order.
+     */
+    pub fn check_frames_parallel(&self, trajectories: &[Trajectory], num_threads: usize) -> Result<Vec<FrameCheck>, &'static str> {
+        let context = self.context(trajectories)?;
+        let num_threads = if num_threads == 0 {
+            thread::available_parallelism().map(|count| count.get()).unwrap_or(1)
+        } else {
+            num_threads
+        };
+        let chunk_size = context.num_frames.div_ceil(num_threads).max(1);
+
+        let chunks: Vec<Vec<FrameCheck>> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..context.num_frames)
+                .step_by(chunk_size)
+                .map(|start| {
+                    let end = (start + chunk_size).min(context.num_frames);
+                    let context = &context;
+                    scope.spawn(move || self.check_frame_range(context, start, end))
+                })
+                .collect();
+
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+
+        Ok(chunks.into_iter().flatten().collect())
+    }
+
+    /**
+     * Returns the time of every frame of the show, starting at the earliest trajectory
+     * point and ending at or before the latest one.
+     */
+    pub fn frame_times(&self, trajectories: &[Trajectory]) -> Result<Vec<f32>, &'static str> {
+        let context = self.context(trajectories)?;
+        Ok((0..context.num_frames).map(|index| context.frame_time(index)).collect())
+    }
+
+    /**
+     * Computes the data of a non-empty trajectory needed by check_drone()
+     */
+    pub fn drone_profile(&self, trajectory: &Trajectory) -> DroneProfile {
+        let points = trajectory.get_points();
+        let (first, last) = (points[0], points[points.len() - 1]);
+
+        DroneProfile {
+            nav_window: altitude_window(trajectory, self.params.min_nav_altitude),
+            airborne_window: altitude_window(trajectory, GROUND_ALTITUDE),
+            takeoff: [first.get_x(), first.get_y()],
+            landing: [last.get_x(), last.get_y()],
+        }
+    }
+
+    fn context<'a>(&self, trajectories: &'a [Trajectory]) -> Result<CheckContext<'a>, &'static str> {
+        if self.fps <= 0.0 {
+            return Err("The frame rate must be positive")
+        }
+        if trajectories.iter().any(|trajectory| trajectory.get_points().is_empty()) {
+            return Err("Every trajectory must have at least one point")
+        }
+
+        let start_time = trajectories.iter()
+            .filter_map(|trajectory| trajectory.start_time())
+            .fold(f32::INFINITY, f32::min);
+        let end_time = trajectories.iter()
+            .filter_map(|trajectory| trajectory.end_time())
+            .fold(f32::NEG_INFINITY, f32::max);
+        let num_frames = if trajectories.is_empty() { 0 } else { frame_count(start_time, end_time, self.fps) };
+        let profiles = trajectories.iter().map(|trajectory| self.drone_profile(trajectory)).collect();
+
+        Ok(CheckContext { trajectories, profiles, start_time, fps: self.fps, num_frames })
+    }
+
+    fn check_frame_range(&self, context: &CheckContext, start: usize, end: usize) -> Vec<FrameCheck> {
+        (start..end).map(|index| self.check_frame(context, index)).collect()
+    }
+
+    fn check_frame(&self, context: &CheckContext, index: usize) -> FrameCheck {
+        let time = context.frame_time(index);
+        let positions = context.positions_at(time);
+        let previous = if index > 0 { Some(context.positions_at(context.frame_time(index - 1))) } else { None };
+
+        let mut frame = FrameCheck::new(time);
+        for (drone, position) in positions.iter().enumerate() {
+            let previous_position = previous.as_ref().map(|previous| &previous[drone]);
+            let flags = self.check_drone(time, position, previous_position, &context.profiles[drone]);
+            frame.set_drone_flags(drone, flags);
+        }
+        self.check_pairs(&mut frame, &positions);
+
+        frame
+    }
+
+    /**
+     * Runs the checks that involve a single drone at a single frame.
+     *
+     * Parameters:
+     *     time: the time of the frame
+     *     position: the position of the drone at the frame
+     *     previous: the position of the drone at the previous frame, if any
+     *     profile: the profile of the drone, see drone_profile()
+     */
+    pub fn check_drone(&self, time: f32, position: &Coordinate3D, previous: Option<&Coordinate3D>, profile: &DroneProfile) -> DroneFlags {
+        let (velocity_xy, velocity_z) = match previous {
+            Some(previous) => velocity(previous, position, self.fps),
+            None => (0.0, 0.0)
+        };
+
+        DroneFlags {
+            over_max_altitude: position[2] > self.params.max_altitude,
+            over_max_velocity_xy: previous.is_some() && velocity_xy > self.params.max_velocity_xy,
+            over_max_velocity_z: previous.is_some() && self.is_over_max_velocity_z(velocity_z),
+            below_min_nav_altitude: is_within(profile.nav_window, time) && position[2] < self.params.min_nav_altitude,
+            outside_vertical_corridor: self.is_outside_vertical_corridor(time, position, profile),
+        }
+    }
+
+    /**
+     * Returns whether the drone drifts horizontally from its takeoff or landing position
+     * while below the minimum navigation altitude, or touches the ground between its first
+     * takeoff and its last touchdown
+     */
+    fn is_outside_vertical_corridor(&self, time: f32, position: &Coordinate3D, profile: &DroneProfile) -> bool {
+        if is_within(profile.airborne_window, time) && position[2] < GROUND_ALTITUDE {
+            return true
+        }
+        if position[2] >= self.params.min_nav_altitude {
+            return false
+        }
+
+        match profile.corridor_center(time) {
+            Some(center) => {
+                let dx = position[0] - center[0];
+                let dy = position[1] - center[1];
+                (dx * dx + dy * dy).sqrt() > self.params.corridor_radius
+            },
+            None => false
+        }
+    }
+
+    /**
+     * Runs the checks that involve every drone at once (distances and minimum altitude)
+     * and stores their outcome in the given frame
+     */
+    pub fn check_pairs(&self, frame: &mut FrameCheck, positions: &[Coordinate3D]) {
+        let (close_pairs, closest) = find_close_pairs(positions, self.params.min_distance);
+        frame.close_pairs = close_pairs;
+        frame.closest_pair = closest.map(|(pair, _)| pair);
+        frame.min_distance = closest.map(|(_, distance)| distance);
+        frame.min_altitude = positions.iter().map(|position| position[2]).reduce(f32::min);
+    }
+
+    fn is_over_max_velocity_z(&self, velocity_z: f32) -> bool {
+        let max_velocity_up = if self.params.max_velocity_z_up > 0.0 {
+            self.params.max_velocity_z_up
+        } else {
+            self.params.max_velocity_z
+        };
+
+        velocity_z > max_velocity_up || velocity_z < -self.params.max_velocity_z
+    }
+}
+
+/**
+ * Data shared by every frame of a single show check
+ */
+struct CheckContext<'a> {
+    trajectories: &'a [Trajectory],
+    profiles: Vec<DroneProfile>,
+    start_time: f32,
+    fps: f32,
+    num_frames: usize,
+}
+
+impl CheckContext<'_> {
+    fn frame_time(&self, index: usize) -> f32 {
+        self.start_time + index as f32 / self.fps
+    }
+
+    fn positions_at(&self, time: f32) -> Vec<Coordinate3D> {
+        self.trajectories.iter().map(|trajectory| position_of(trajectory, time)).collect()
+    }
+}
+
+/**
+ * Merges the outcome of consecutive frames into a single SafetyCheckResult, in frame order
+ */
+pub fn merge_frame_checks(frames: &[FrameCheck], trajectories: &[Trajectory]) -> SafetyCheckResult {
+    let mut result = SafetyCheckResult::new_empty();
+    for frame in frames {
+        result.extend(frame.as_result(trajectories));
+    }
+
+    result
+}
+
+/**
+ * Returns the position of a non-empty trajectory at the given time as a coordinate
+ */
+pub fn position_of(trajectory: &Trajectory, time: f32) -> Coordinate3D {
+    let point = trajectory.position_at(time).unwrap();
+    [point.get_x(), point.get_y(), point.get_z()]
+}
+
+/**
+ * Returns the horizontal speed and the vertical velocity of a drone that moved between two
+ * positions in one frame
+ */
+pub fn velocity(previous: &Coordinate3D, current: &Coordinate3D, fps: f32) -> (f32, f32) {
+    let dx = current[0] - previous[0];
+    let dy = current[1] - previous[1];
+    let dz = current[2] - previous[2];
+
+    ((dx * dx + dy * dy).sqrt() * fps, dz * fps)
+}
+
+pub fn distance(p: &Coordinate3D, q: &Coordinate3D) -> f32 {
+    let dx = p[0] - q[0];
+    let dy = p[1] - q[1];
+    let dz = p[2] - q[2];
+
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+/**
+ * Returns the time window between the first moment the trajectory reaches the given
+ * altitude and the last moment it is still at or above it, or None if it never does.
+ *
+ * With the minimum navigation altitude this is the navigation window of the drone; it is
+ * allowed to be below that altitude outside the window, i.e. during takeoff and landing.
+ */
+pub fn altitude_window(trajectory: &Trajectory, altitude: f32) -> Option<(f32, f32)> {
+    let points = trajectory.get_points();
+    let first = points.iter().position(|point| point.get_z() >= altitude)?;
+    let last = points.iter().rposition(|point| point.get_z() >= altitude)?;
+
+    let crossing = |a: usize, b: usize| {
+        let (p, q) = (points[a], points[b]);
+        let ratio = (altitude - p.get_z()) / (q.get_z() - p.get_z());
+        p.get_t() + ratio * (q.get_t() - p.get_t())
+    };
+    let start = if first == 0 { points[0].get_t() } else { crossing(first - 1, first) };
+    let end = if last == points.len() - 1 { points[last].get_t() } else { crossing(last, last + 1) };
+
+    Some((start, end))
+}
+
+pub fn is_within(window: Option<(f32, f32)>, time: f32) -> bool {
+    match window {
+        Some((start, end)) => time > start && time < end,
+        None => false
+    }
+}
+
+/**
+ * Data of a single drone that every frame check needs, computed once per trajectory
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DroneProfile {
+    //Time window where the drone is at or above the minimum navigation altitude
+    pub nav_window: Option<(f32, f32)>,
+    //Time window between the first takeoff and the last touchdown of the drone
+    pub airborne_window: Option<(f32, f32)>,
+    //Horizontal position of the first point of the trajectory
+    pub takeoff: [f32; 2],
+    //Horizontal position of the last point of the trajectory
+    pub landing: [f32; 2],
+}
+
+impl DroneProfile {
+    /**
+     * Returns the horizontal position the drone must stay close to while it is below the
+     * minimum navigation altitude at the given time: the takeoff position before reaching
+     * the navigation altitude and the landing position after leaving it, or None while
+     * it is navigating.
+     */
+    pub fn corridor_center(&self, time: f32) -> Option<[f32; 2]> {
+        match self.nav_window {
+            None => Some(self.takeoff),
+            Some((start, _)) if time <= start => Some(self.takeoff),
+            Some((_, end)) if time >= end => Some(self.landing),
+            Some(_) => None
+        }
+    }
+}
+
+//A pair of drone indices together with their distance
+pub type IndexedPair = ((usize, usize), f32);
+
+/**
+ * Finds every pair of points closer than the given distance and the closest pair overall,
+ * sweeping the points sorted along the X axis.
+ *
+ * Returns:
+ *     the close pairs as (lower index, higher index) sorted by index, and the closest pair
+ *     together with its distance if there are at least two points
+ */
+pub fn find_close_pairs(positions: &[Coordinate3D], min_distance: f32) -> (Vec<(usize, usize)>, Option<IndexedPair>) {
+    let mut order: Vec<usize> = (0..positions.len()).collect();
+    order.sort_by(|a, b| sweep_cmp(positions, *a, *b));
+
+    let mut close_pairs: Vec<(usize, usize)> = Vec::new();
+    let mut closest: Option<IndexedPair> = None;
+
+    for (a, i) in order.iter().enumerate() {
+        for j in &order[a + 1..] {
+            let best = closest.map_or(f32::INFINITY, |(_, distance)| distance);
+            if positions[*j][0] - positions[*i][0] >= best.max(min_distance) {
+                break;
+            }
+
+            let pair = ((*i).min(*j), (*i).max(*j));
+            let d = distance(&positions[*i], &positions[*j]);
+            if d < min_distance {
+                close_pairs.push(pair);
+            }
+            if d < best {
+                closest = Some((pair, d));
+            }
+        }
+    }
+
+    close_pairs.sort();
+    (close_pairs, closest)
+}
+
+/**
+ * Returns whether a pair of drones is closer than another one. Pairs at the same distance
+ * are ordered as find_close_pairs() visits them, so the first one it finds wins.
+ */
+pub fn is_closer_pair(positions: &[Coordinate3D], candidate: IndexedPair, best: IndexedPair) -> bool {
+    let sweep_pair = |(i, j): (usize, usize)| if sweep_cmp(positions, i, j) == Ordering::Greater { (j, i) } else { (i, j) };
+    let (a, b) = (sweep_pair(candidate.0), sweep_pair(best.0));
+
+    candidate.1 < best.1
+        || (candidate.1 == best.1 && sweep_cmp(positions, a.0, b.0).then_with(|| sweep_cmp(positions, a.1, b.1)) == Ordering::Less)
+}
+
+/* Order of the drones in the sweep of find_close_pairs(): along the X axis, then by index */
+fn sweep_cmp(positions: &[Coordinate3D], a: usize, b: usize) -> Ordering {
+    positions[a][0].total_cmp(&positions[b][0]).then(a.cmp(&b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::point::Point4D;
+
+    fn trajectory(points: &[(f32, f32, f32, f32)]) -> Trajectory {
+        Trajectory::new(points.iter().map(|(t, x, y, z)| Point4D::new(*t, *x, *y, *z)).collect())
+    }
+
+    /*
+     * Show with close pairs, fast climbs, fast horizontal moves, a mid-show dip below the
+     * navigation altitude and a takeoff that drifts out of its corridor
+     */
+    fn show() -> Vec<Trajectory> {
+        vec![
+            trajectory(&[(0.0, 0.0, 0.0, 0.0), (2.0, 0.0, 0.0, 10.0), (4.0, 0.0, 0.0, 10.0)]),
+            trajectory(&[(0.0, 2.0, 0.0, 10.0), (4.0, 2.0, 0.0, 10.0)]),
+            trajectory(&[(0.0, 20.0, 0.0, 10.0), (1.0, 40.0, 0.0, 10.0), (2.0, 40.0, 0.0, 1.0), (3.0, 40.0, 0.0, 10.0)]),
+            trajectory(&[(0.5, -20.0, 0.0, 5.0), (3.5, -18.0, 1.0, 5.0)]),
+            trajectory(&[(0.0, 0.0, 20.0, 0.0), (2.0, 4.0, 20.0, 1.0), (4.0, 4.0, 20.0, 10.0)]),
+        ]
+    }
+
+    /* Indices of the drones flagged outside their vertical corridor in any frame */
+    fn outside_corridor(trajectories: &[Trajectory]) -> Vec<usize> {
+        let frames = SafetyChecker::new(SafetyCheckParams::new(), 4.0).check_frames_parallel(trajectories, 1).unwrap();
+        let mut drones: Vec<usize> = frames.iter().flat_map(|frame| frame.outside_vertical_corridor.clone()).collect();
+        drones.sort();
+        drones.dedup();
+        drones
+    }
+
+    #[test]
+    fn fixture_has_violations() {
+        let result = SafetyChecker::new(SafetyCheckParams::new(), 4.0).check(&show()).unwrap();
+        assert!(!result.all_close_pairs.is_empty());
+        assert!(!result.drones_over_max_velocity_xy.is_empty());
+        assert!(!result.drones_over_max_velocity_z.is_empty());
+        assert!(!result.drones_below_min_nav_altitude.is_empty());
+        assert!(!result.drones_outside_vertical_corridor.is_empty());
+    }
+
+    #[test]
+    fn vertical_corridor_violations() {
+        let trajectories = vec![
+            //Straight takeoff and landing, moving only at the navigation altitude
+            trajectory(&[(0.0, 0.0, 0.0, 0.0), (4.0, 0.0, 0.0, 10.0), (10.0, 5.0, 0.0, 10.0), (14.0, 5.0, 0.0, 0.0)]),
+            //Drifts 4 meters while still 1 meter above the ground
+            trajectory(&[(0.0, 20.0, 0.0, 0.0), (2.0, 24.0, 0.0, 1.0), (6.0, 24.0, 0.0, 11.0), (10.0, 24.0, 0.0, 1.0), (12.0, 24.0, 0.0, 0.0)]),
+            //Touches the ground in the middle of the show, right where it took off
+            trajectory(&[(0.0, 40.0, 0.0, 0.0), (4.0, 40.0, 0.0, 10.0), (8.0, 40.0, 0.0, 0.0), (12.0, 40.0, 0.0, 10.0), (16.0, 40.0, 0.0, 0.0)]),
+            //Lands 4 meters away from where it leaves the navigation altitude
+            trajectory(&[(0.0, 60.0, 0.0, 0.0), (4.0, 60.0, 0.0, 10.0), (7.0, 60.0, 0.0, 2.0), (9.0, 64.0, 0.0, 0.0)]),
+        ];
+
+        assert_eq!(outside_corridor(&trajectories), vec![1, 2, 3]);
+        assert!(outside_corridor(&trajectories[..1]).is_empty());
+    }
+
+    #[test]
+    fn corridor_center_follows_navigation_window() {
+        let profile = DroneProfile { nav_window: Some((2.0, 8.0)), airborne_window: Some((0.5, 9.5)), takeoff: [1.0, 2.0], landing: [3.0, 4.0] };
+
+        assert_eq!(profile.corridor_center(0.0), Some([1.0, 2.0]));
+        assert_eq!(profile.corridor_center(2.0), Some([1.0, 2.0]));
+        assert_eq!(profile.corridor_center(5.0), None);
+        assert_eq!(profile.corridor_center(8.0), Some([3.0, 4.0]));
+        assert_eq!(profile.corridor_center(10.0), Some([3.0, 4.0]));
+
+        let low = DroneProfile { nav_window: None, ..profile };
+        assert_eq!(low.corridor_center(5.0), Some([1.0, 2.0]));
+    }
+
+    #[test]
+    fn parallel_check_matches_sequential_check() {
+        let trajectories = show();
+        let checker = SafetyChecker::new(SafetyCheckParams::new(), 4.0);
+        let expected = checker.check(&trajectories).unwrap();
+        let num_frames = checker.frame_times(&trajectories).unwrap().len();
+
+        for num_threads in [0, 1, 3, num_frames + 5] {
+            assert_eq!(checker.check_parallel(&trajectories, num_threads).unwrap(), expected, "{} threads", num_threads);
+        }
+    }
+
+    #[test]
+    fn parallel_check_of_empty_show() {
+        let checker = SafetyChecker::new(SafetyCheckParams::new(), 4.0);
+        assert_eq!(checker.check_parallel(&[], 3).unwrap(), checker.check(&[]).unwrap());
+    }
+}
@@ -0,0 +1,214 @@
+use crate::model::safety_check::SafetyCheckResult;
+use crate::model::trajectory::Trajectory;
+
+use super::checker::{distance, is_closer_pair, merge_frame_checks, position_of, DroneProfile, FrameCheck, SafetyChecker};
+
+/**
+ * Safety check of a show that is kept up to date while the trajectories of its drones
+ * are edited.
+ *
+ * The outcome of every frame is kept in memory so that, when a single drone changes,
+ * only the checks involving that drone within the edited time window are run again.
+ */
+pub struct IncrementalSafetyCheck {
+    checker: SafetyChecker,
+    trajectories: Vec<Trajectory>,
+    profiles: Vec<DroneProfile>,
+    frames: Vec<FrameCheck>,
+    //Earliest and latest timestamps of the show, which define the frame grid
+    span: (f32, f32),
+    //Number of threads used for full checks; 0 uses the available parallelism
+    num_threads: usize,
+}
+
+impl IncrementalSafetyCheck {
+    /**
+     * Runs a full check of the show and keeps its outcome for later updates.
+     *
+     * Parameters:
+     *     checker: the checker holding the safety parameters and frame rate
+     *     trajectories: the trajectory of each drone of the show
+     *     num_threads: number of threads used for full checks; 0 uses the available parallelism
+     */
+    pub fn new(checker: SafetyChecker, trajectories: Vec<Trajectory>, num_threads: usize) -> Result<Self, &'static str> {
+        let frames = checker.check_frames_parallel(&trajectories, num_threads)?;
+        let profiles = compute_profiles(&checker, &trajectories);
+        let span = show_span(&trajectories);
+
+        Ok(Self { checker, trajectories, profiles, frames, span, num_threads })
+    }
+
+    pub fn get_trajectories(&self) -> &[Trajectory] {
+        &self.trajectories
+    }
+
+    pub fn get_frames(&self) -> &[FrameCheck] {
+        &self.frames
+    }
+
+    /** Returns the result of the check of the whole show in its current state */
+    pub fn result(&self) -> SafetyCheckResult {
+        merge_frame_checks(&self.frames, &self.trajectories)
+    }
+
+    /**
+     * Replaces the trajectory of a drone and re-runs only the checks affected by the change.
+     *
+     * If the new trajectory changes the start or the end of the show the frame grid changes
+     * as well, so the whole show is checked again.
+     *
+     * Parameters:
+     *     drone: the index of the drone whose trajectory changed
+     *     trajectory: the new trajectory of the drone
+     *     start: start of the edited time window, in seconds
+     *     end: end of the edited time window, in seconds. The window must cover every
+     *          time where the position of the drone changed
+     *
+     * Returns:
+     *     the updated result of the check of the whole show
+     */
+    pub fn update_drone(&mut self, drone: usize, trajectory: Trajectory, start: f32, end: f32) -> Result<SafetyCheckResult, &'static str> {
+        if drone >= self.trajectories.len() {
+            return Err("Drone index out of range")
+        }
+        if trajectory.get_points().is_empty() {
+            return Err("Every trajectory must have at least one point")
+        }
+
+        self.trajectories[drone] = trajectory;
+        let span = show_span(&self.trajectories);
+        if span != self.span {
+            self.frames = self.checker.check_frames_parallel(&self.trajectories, self.num_threads)?;
+            self.profiles = compute_profiles(&self.checker, &self.trajectories);
+            self.span = span;
+            return Ok(self.result())
+        }
+
+        let profile = self.checker.drone_profile(&self.trajectories[drone]);
+        let profile_changed = profile != self.profiles[drone];
+        self.profiles[drone] = profile;
+
+        //The velocity of the first frame after the window depends on the last frame inside it
+        let first = self.frames.partition_point(|frame| frame.time < start);
+        let last = (self.frames.partition_point(|frame| frame.time <= end) + 1).min(self.frames.len());
+
+        //A different profile may change the altitude and corridor checks of the drone anywhere
+        let (flags_first, flags_last) = if profile_changed { (0, self.frames.len()) } else { (first, last) };
+        for index in flags_first..flags_last {
+            self.update_drone_flags(drone, index);
+        }
+        for index in first..last {
+            self.update_pairs(drone, index);
+        }
+
+        Ok(self.result())
+    }
+
+    fn update_drone_flags(&mut self, drone: usize, index: usize) {
+        let trajectory = &self.trajectories[drone];
+        let time = self.frames[index].time;
+        let position = position_of(trajectory, time);
+        let previous = if index > 0 { Some(position_of(trajectory, self.frames[index - 1].time)) } else { None };
+
+        let flags = self.checker.check_drone(time, &position, previous.as_ref(), &self.profiles[drone]);
+        self.frames[index].set_drone_flags(drone, flags);
+    }
+
+    /**
+     * Updates the distance checks of a frame after a drone moved. Pairs that do not involve
+     * the drone are still valid, unless the closest pair of the frame involved it, in which
+     * case the frame is checked again from scratch. Ties are broken as in a full check.
+     */
+    fn update_pairs(&mut self, drone: usize, index: usize) {
+        let frame = &mut self.frames[index];
+        let positions: Vec<_> = self.trajectories.iter().map(|trajectory| position_of(trajectory, frame.time)).collect();
+
+        if let Some((i, j)) = frame.closest_pair {
+            if i == drone || j == drone {
+                self.checker.check_pairs(frame, &positions);
+                return;
+            }
+        }
+
+        let min_distance = self.checker.get_params().min_distance;
+        frame.close_pairs.retain(|(i, j)| *i != drone && *j != drone);
+        for other in (0..positions.len()).filter(|other| *other != drone) {
+            let pair = (drone.min(other), drone.max(other));
+            let d = distance(&positions[drone], &positions[other]);
+            if d < min_distance {
+                frame.close_pairs.push(pair);
+            }
+            let is_closest = match (frame.closest_pair, frame.min_distance) {
+                (Some(best_pair), Some(best)) => is_closer_pair(&positions, (pair, d), (best_pair, best)),
+                _ => true
+            };
+            if is_closest {
+                frame.closest_pair = Some(pair);
+                frame.min_distance = Some(d);
+            }
+        }
+        frame.close_pairs.sort();
+        frame.min_altitude = positions.iter().map(|position| position[2]).reduce(f32::min);
+    }
+}
+
+fn compute_profiles(checker: &SafetyChecker, trajectories: &[Trajectory]) -> Vec<DroneProfile> {
+    trajectories.iter().map(|trajectory| checker.drone_profile(trajectory)).collect()
+}
+
+fn show_span(trajectories: &[Trajectory]) -> (f32, f32) {
+    let start = trajectories.iter().filter_map(|trajectory| trajectory.start_time()).fold(f32::INFINITY, f32::min);
+    let end = trajectories.iter().filter_map(|trajectory| trajectory.end_time()).fold(f32::NEG_INFINITY, f32::max);
+
+    (start, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::point::Point4D;
+    use crate::model::safety_check::SafetyCheckParams;
+
+    fn trajectory(points: &[(f32, f32, f32, f32)]) -> Trajectory {
+        Trajectory::new(points.iter().map(|(t, x, y, z)| Point4D::new(*t, *x, *y, *z)).collect())
+    }
+
+    fn show() -> Vec<Trajectory> {
+        vec![
+            trajectory(&[(0.0, 0.0, 0.0, 10.0), (4.0, 0.0, 0.0, 10.0)]),
+            trajectory(&[(0.0, 5.0, 0.0, 10.0), (4.0, 5.0, 0.0, 10.0)]),
+            trajectory(&[(0.0, 20.0, 0.0, 10.0), (4.0, 20.0, 0.0, 10.0)]),
+            trajectory(&[(0.0, 40.0, 0.0, 10.0), (4.0, 40.0, 0.0, 10.0)]),
+        ]
+    }
+
+    fn assert_matches_full_check(check: &IncrementalSafetyCheck) {
+        let checker = SafetyChecker::new(SafetyCheckParams::new(), 4.0);
+        assert_eq!(check.result(), checker.check(check.get_trajectories()).unwrap());
+    }
+
+    #[test]
+    fn update_breaks_ties_as_full_check() {
+        let mut check = IncrementalSafetyCheck::new(SafetyChecker::new(SafetyCheckParams::new(), 4.0), show(), 2).unwrap();
+
+        //Drone 2 moves as far from drone 0 as drone 1 is, but comes first along the X axis
+        check.update_drone(2, trajectory(&[(0.0, 20.0, 0.0, 10.0), (2.0, -5.0, 0.0, 10.0), (4.0, -5.0, 0.0, 10.0)]), 0.0, 4.0).unwrap();
+        assert_matches_full_check(&check);
+        assert_eq!(check.get_frames().last().unwrap().closest_pair, Some((0, 2)));
+    }
+
+    #[test]
+    fn update_matches_full_check() {
+        let mut check = IncrementalSafetyCheck::new(SafetyChecker::new(SafetyCheckParams::new(), 4.0), show(), 2).unwrap();
+
+        check.update_drone(3, trajectory(&[(0.0, 40.0, 0.0, 10.0), (1.0, 6.0, 1.0, 10.0), (2.0, 6.0, 1.0, 1.0), (4.0, 40.0, 0.0, 10.0)]), 0.0, 4.0).unwrap();
+        assert_matches_full_check(&check);
+
+        check.update_drone(1, trajectory(&[(0.0, 5.0, 0.0, 10.0), (3.0, 5.0, 0.0, 10.0), (3.5, 25.0, 0.0, 10.0), (4.0, 5.0, 0.0, 10.0)]), 3.0, 4.0).unwrap();
+        assert_matches_full_check(&check);
+
+        //Moving the end of the show changes the frame grid
+        check.update_drone(0, trajectory(&[(0.0, 0.0, 0.0, 10.0), (5.0, 0.0, 0.0, 10.0)]), 4.0, 5.0).unwrap();
+        assert_matches_full_check(&check);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod checker;
+pub mod incremental;
+pub mod resolution;
+pub mod battery;
@@ -0,0 +1,359 @@
+use std::collections::HashSet;
+
+use crate::model::point::Point4D;
+use crate::model::trajectory::Trajectory;
+
+use super::checker::{distance, position_of, DroneProfile, FrameCheck, SafetyChecker};
+
+//Fractions of the duration of a transition tried when delaying the departure of a drone
+const DELAY_FRACTIONS: [f32; 5] = [0.1, 0.2, 0.3, 0.4, 0.5];
+
+//Multiples of the minimum distance tried as vertical offsets, upwards first
+const OFFSET_FACTORS: [f32; 4] = [1.0, -1.0, 2.0, -2.0];
+
+//Fraction of the duration of a transition spent climbing to (and descending from) an offset layer
+const OFFSET_RAMP: f32 = 0.25;
+
+/**
+ * The kind of change proposed to untangle two drones that get too close
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ResolutionKind {
+    //Keep the drone still at the start of the transition for the given seconds
+    DelayDeparture { drone: usize, delay: f32 },
+    //Fly the transition on a layer shifted vertically by the given meters
+    VerticalOffset { drone: usize, offset: f32 },
+    //Exchange the targets, and the rest of the show after them, between two drones
+    SwapTargets { first: usize, second: usize },
+}
+
+/**
+ * A proposed fix for a pair of drones that violate the minimum distance, already verified
+ * against the safety checks.
+ */
+#[derive(Clone)]
+pub struct ResolutionSuggestion {
+    //The pair of drones in conflict, as (lower index, higher index)
+    pub pair: (usize, usize),
+    //The time of the first frame where the pair is too close, in seconds
+    pub time: f32,
+    pub kind: ResolutionKind,
+    //The new trajectories of the drones changed by the fix, with their index
+    pub trajectories: Vec<(usize, Trajectory)>,
+    //The smallest distance between the changed drones and any other drone during the transition
+    pub min_distance: f32,
+}
+
+/**
+ * Proposes fixes for every pair of drones that gets closer than the minimum distance.
+ *
+ * For each conflicting pair, delaying the departure of either drone, moving either drone
+ * to a vertical offset layer, and swapping the targets of both drones are tried on the
+ * transition where the conflict starts. Only fixes where the changed drones keep the
+ * minimum distance to every other drone and pass the velocity and altitude checks during
+ * the transition, and cause no more violations than the original drones for the rest of
+ * the show, are returned.
+ *
+ * Parameters:
+ *     checker: the checker holding the safety parameters and frame rate
+ *     trajectories: the trajectory of each drone of the show
+ *     frames: the outcome of every frame of the show, see SafetyChecker::check_frames_parallel()
+ *
+ * Returns:
+ *     the verified suggestions, grouped by conflicting pair in order of first conflict
+ */
+pub fn suggest_resolutions(checker: &SafetyChecker, trajectories: &[Trajectory], frames: &[FrameCheck]) -> Vec<ResolutionSuggestion> {
+    let mut conflicts: Vec<((usize, usize), f32)> = Vec::new();
+    let mut known: HashSet<(usize, usize)> = HashSet::new();
+    for frame in frames {
+        for pair in &frame.close_pairs {
+            if known.insert(*pair) {
+                conflicts.push((*pair, frame.time));
+            }
+        }
+    }
+
+    let mut suggestions: Vec<ResolutionSuggestion> = Vec::new();
+    for (pair, time) in conflicts {
+        for kind in candidate_kinds(checker, trajectories, pair, time) {
+            if let Some(suggestion) = try_resolution(checker, trajectories, pair, time, kind) {
+                suggestions.push(suggestion);
+            }
+        }
+    }
+
+    suggestions
+}
+
+fn candidate_kinds(checker: &SafetyChecker, trajectories: &[Trajectory], pair: (usize, usize), time: f32) -> Vec<ResolutionKind> {
+    let mut kinds: Vec<ResolutionKind> = Vec::new();
+
+    for drone in [pair.0, pair.1] {
+        if let Some((start, end)) = transition_at(&trajectories[drone], time) {
+            for fraction in DELAY_FRACTIONS {
+                kinds.push(ResolutionKind::DelayDeparture { drone, delay: fraction * (end - start) });
+            }
+        }
+    }
+    for factor in OFFSET_FACTORS {
+        for drone in [pair.0, pair.1] {
+            kinds.push(ResolutionKind::VerticalOffset { drone, offset: factor * checker.get_params().min_distance });
+        }
+    }
+    kinds.push(ResolutionKind::SwapTargets { first: pair.0, second: pair.1 });
+
+    kinds
+}
+
+fn try_resolution(checker: &SafetyChecker, trajectories: &[Trajectory], pair: (usize, usize), time: f32, kind: ResolutionKind) -> Option<ResolutionSuggestion> {
+    let changed: Vec<(usize, Trajectory)> = match kind {
+        ResolutionKind::DelayDeparture { drone, delay } => vec![(drone, delay_departure(&trajectories[drone], time, delay)?)],
+        ResolutionKind::VerticalOffset { drone, offset } => vec![(drone, vertical_offset(&trajectories[drone], time, offset)?)],
+        ResolutionKind::SwapTargets { first, second } => {
+            let (first_trajectory, second_trajectory) = swap_targets(&trajectories[first], &trajectories[second], time)?;
+            vec![(first, first_trajectory), (second, second_trajectory)]
+        }
+    };
+    let window = changed.iter()
+        .filter_map(|(drone, _)| transition_at(&trajectories[*drone], time))
+        .reduce(|a, b| (a.0.min(b.0), a.1.max(b.1)))?;
+
+    let min_distance = verify(checker, trajectories, &changed, window)?;
+    Some(ResolutionSuggestion { pair, time, kind, trajectories: changed, min_distance })
+}
+
+/**
+ * Checks the changed drones against every other drone from the start of the given window to
+ * the end of the show. Within the window the changed drones must pass every check; after it
+ * they must not cause more violations than the original drones, since a fix may change the
+ * rest of the show (e.g. when swapping targets).
+ *
+ * Returns:
+ *     the smallest distance between a changed drone and any other drone within the window,
+ *     or None if the changed drones fail the checks
+ */
+fn verify(checker: &SafetyChecker, trajectories: &[Trajectory], changed: &[(usize, Trajectory)], window: (f32, f32)) -> Option<f32> {
+    let fps = checker.get_fps();
+    let original: Vec<&Trajectory> = trajectories.iter().collect();
+    let mut candidate = original.clone();
+    for (drone, trajectory) in changed {
+        candidate[*drone] = trajectory;
+    }
+    let drones: Vec<usize> = changed.iter().map(|(drone, _)| *drone).collect();
+    let original_profiles: Vec<_> = drones.iter().map(|drone| checker.drone_profile(original[*drone])).collect();
+    let candidate_profiles: Vec<_> = changed.iter().map(|(_, trajectory)| checker.drone_profile(trajectory)).collect();
+
+    let end = candidate.iter().filter_map(|trajectory| trajectory.end_time()).fold(window.1, f32::max);
+    let num_frames = ((end - window.0) * fps).ceil() as usize + 1;
+    let mut min_distance = f32::INFINITY;
+    for index in 0..num_frames {
+        let time = (window.0 + index as f32 / fps).min(end);
+        let (violations, distance) = count_violations(checker, &candidate, &drones, &candidate_profiles, time);
+
+        if time <= window.1 {
+            if violations > 0 { return None }
+            min_distance = min_distance.min(distance);
+        } else if violations > count_violations(checker, &original, &drones, &original_profiles, time).0 {
+            return None
+        }
+    }
+
+    Some(min_distance)
+}
+
+/*
+ * Counts the failed single drone checks and the drones closer than the minimum distance of
+ * the given drones at the given time, and returns them with the smallest distance found
+ */
+fn count_violations(checker: &SafetyChecker, trajectories: &[&Trajectory], drones: &[usize], profiles: &[DroneProfile], time: f32) -> (usize, f32) {
+    let fps = checker.get_fps();
+    let positions: Vec<_> = trajectories.iter().map(|trajectory| position_of(trajectory, time)).collect();
+    let mut violations = 0;
+    let mut min_distance = f32::INFINITY;
+
+    for (drone, profile) in drones.iter().zip(profiles) {
+        let previous = position_of(trajectories[*drone], time - 1.0 / fps);
+        if checker.check_drone(time, &positions[*drone], Some(&previous), profile).has_violation() {
+            violations += 1;
+        }
+
+        for (other, position) in positions.iter().enumerate() {
+            if other != *drone {
+                let distance = distance(&positions[*drone], position);
+                if distance < checker.get_params().min_distance { violations += 1 }
+                min_distance = min_distance.min(distance);
+            }
+        }
+    }
+
+    (violations, min_distance)
+}
+
+/**
+ * Returns the index of the first point of the trajectory segment that contains the given time
+ */
+fn segment_at(trajectory: &Trajectory, time: f32) -> Option<usize> {
+    let points = trajectory.get_points();
+    let index = points.partition_point(|point| point.get_t() <= time);
+    if index == 0 || index >= points.len() { None } else { Some(index - 1) }
+}
+
+/**
+ * Returns the start and end time of the trajectory segment that contains the given time
+ */
+fn transition_at(trajectory: &Trajectory, time: f32) -> Option<(f32, f32)> {
+    let points = trajectory.get_points();
+    let index = segment_at(trajectory, time)?;
+
+    Some((points[index].get_t(), points[index + 1].get_t()))
+}
+
+/**
+ * Holds the drone at the start of the transition containing the given time for a while and
+ * then flies it faster so it still arrives on time
+ */
+fn delay_departure(trajectory: &Trajectory, time: f32, delay: f32) -> Option<Trajectory> {
+    let mut points = trajectory.get_points().to_vec();
+    let index = segment_at(trajectory, time)?;
+    let start = points[index];
+    if delay <= 0.0 || start.get_t() + delay >= points[index + 1].get_t() { return None }
+
+    points.insert(index + 1, start.as_3d().at_time(start.get_t() + delay));
+    Some(Trajectory::new(points))
+}
+
+/**
+ * Flies the transition containing the given time on a layer shifted vertically, ramping
+ * in and out of the layer at the start and the end of the transition
+ */
+fn vertical_offset(trajectory: &Trajectory, time: f32, offset: f32) -> Option<Trajectory> {
+    let mut points = trajectory.get_points().to_vec();
+    let index = segment_at(trajectory, time)?;
+    let (start, end) = (points[index], points[index + 1]);
+    let ramp = OFFSET_RAMP * (end.get_t() - start.get_t());
+    if ramp <= 0.0 { return None }
+
+    let lifted = |point: Point4D, t: f32| Point4D::new(t, point.get_x(), point.get_y(), point.get_z() + offset);
+    points.insert(index + 1, lifted(start, start.get_t() + ramp));
+    points.insert(index + 2, lifted(end, end.get_t() - ramp));
+    Some(Trajectory::new(points))
+}
+
+/**
+ * Exchanges the targets of two drones that fly a transition over the same time window. Each
+ * drone continues with the rest of the show of the other drone after the transition.
+ */
+fn swap_targets(first: &Trajectory, second: &Trajectory, time: f32) -> Option<(Trajectory, Trajectory)> {
+    let first_index = segment_at(first, time)?;
+    let second_index = segment_at(second, time)?;
+    if transition_at(first, time)? != transition_at(second, time)? { return None }
+
+    let (first_points, second_points) = (first.get_points(), second.get_points());
+    let swapped = |head: &[Point4D], tail: &[Point4D]| Trajectory::new([head, tail].concat());
+
+    Some((
+        swapped(&first_points[..=first_index], &second_points[second_index + 1..]),
+        swapped(&second_points[..=second_index], &first_points[first_index + 1..])
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::safety_check::SafetyCheckParams;
+
+    fn trajectory(points: &[(f32, f32, f32, f32)]) -> Trajectory {
+        Trajectory::new(points.iter().map(|(t, x, y, z)| Point4D::new(*t, *x, *y, *z)).collect())
+    }
+
+    fn checker() -> SafetyChecker {
+        SafetyChecker::new(SafetyCheckParams::new(), 4.0)
+    }
+
+    /* Two drones whose paths cross at the origin at 5 s */
+    fn crossing() -> Vec<Trajectory> {
+        vec![
+            trajectory(&[(0.0, -10.0, 0.0, 10.0), (10.0, 10.0, 0.0, 10.0)]),
+            trajectory(&[(0.0, 0.0, -10.0, 10.0), (10.0, 0.0, 10.0, 10.0)]),
+        ]
+    }
+
+    fn suggestions(trajectories: &[Trajectory]) -> Vec<ResolutionSuggestion> {
+        let checker = checker();
+        let frames = checker.check_frames_parallel(trajectories, 1).unwrap();
+        suggest_resolutions(&checker, trajectories, &frames)
+    }
+
+    /* Asserts that some suggestion of the kind exists and that each of them fixes the whole show */
+    fn assert_resolves(trajectories: &[Trajectory], is_kind: impl Fn(&ResolutionKind) -> bool) {
+        let suggestions: Vec<_> = suggestions(trajectories).into_iter().filter(|suggestion| is_kind(&suggestion.kind)).collect();
+        assert!(!suggestions.is_empty());
+
+        for suggestion in suggestions {
+            let mut fixed = trajectories.to_vec();
+            for (drone, trajectory) in &suggestion.trajectories {
+                fixed[*drone] = trajectory.clone();
+            }
+            let result = checker().check(&fixed).unwrap();
+
+            assert_eq!(suggestion.pair, (0, 1));
+            assert!(suggestion.min_distance >= checker().get_params().min_distance);
+            assert!(result.all_close_pairs.is_empty(), "{:?} leaves close pairs", suggestion.kind);
+            assert!(result.drones_over_max_velocity_xy.is_empty(), "{:?} is too fast", suggestion.kind);
+            assert!(result.drones_over_max_velocity_z.is_empty(), "{:?} climbs too fast", suggestion.kind);
+        }
+    }
+
+    #[test]
+    fn delay_departure_clears_crossing() {
+        assert_resolves(&crossing(), |kind| matches!(kind, ResolutionKind::DelayDeparture { .. }));
+    }
+
+    #[test]
+    fn vertical_offset_clears_crossing() {
+        assert_resolves(&crossing(), |kind| matches!(kind, ResolutionKind::VerticalOffset { .. }));
+    }
+
+    #[test]
+    fn swap_targets_clears_head_on_conflict() {
+        let head_on = vec![
+            trajectory(&[(0.0, -10.0, 0.0, 10.0), (10.0, 10.0, 0.0, 10.0), (20.0, 10.0, 0.0, 10.0)]),
+            trajectory(&[(0.0, 10.0, 0.0, 10.0), (10.0, -10.0, 0.0, 10.0), (20.0, -10.0, 0.0, 10.0)]),
+        ];
+
+        assert_resolves(&head_on, |kind| *kind == ResolutionKind::SwapTargets { first: 0, second: 1 });
+    }
+
+    #[test]
+    fn each_conflict_is_resolved_once() {
+        let found = suggestions(&crossing());
+
+        assert!(found.iter().all(|suggestion| suggestion.pair == (0, 1) && suggestion.time == found[0].time));
+        assert!(found.iter().filter(|suggestion| matches!(suggestion.kind, ResolutionKind::SwapTargets { .. })).count() <= 1);
+        assert!(suggestions(&[crossing()[0].clone()]).is_empty());
+    }
+
+    #[test]
+    fn fix_that_breaks_a_later_frame_is_rejected() {
+        let trajectories = vec![
+            trajectory(&[(0.0, -10.0, 0.0, 10.0), (20.0, -10.0, 0.0, 10.0)]),
+            trajectory(&[(0.0, 10.0, 0.0, 10.0), (10.0, 10.0, 5.0, 10.0), (20.0, 10.0, 5.0, 10.0)]),
+        ];
+        //Same transition, but then flies next to the first drone
+        let changed = trajectory(&[(0.0, 10.0, 0.0, 10.0), (10.0, 10.0, 5.0, 10.0), (20.0, -10.0, 2.0, 10.0)]);
+
+        assert!(verify(&checker(), &trajectories, &[(1, trajectories[1].clone())], (0.0, 10.0)).is_some());
+        assert!(verify(&checker(), &trajectories, &[(1, changed)], (0.0, 10.0)).is_none());
+    }
+
+    #[test]
+    fn later_conflicts_of_the_original_show_do_not_block_fixes() {
+        let mut trajectories = crossing();
+        trajectories[1] = trajectory(&[(0.0, 0.0, -10.0, 10.0), (10.0, 0.0, 10.0, 10.0), (20.0, 0.0, 10.0, 10.0)]);
+        //Comes close to the second drone at the end of the show
+        trajectories.push(trajectory(&[(0.0, 20.0, 12.0, 10.0), (12.0, 20.0, 12.0, 10.0), (17.0, 0.0, 12.0, 10.0), (20.0, 0.0, 12.0, 10.0)]));
+
+        let found = suggestions(&trajectories);
+        assert!(found.iter().any(|suggestion| suggestion.pair == (0, 1)));
+    }
+}
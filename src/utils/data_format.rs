@@ -1,5 +1,10 @@
+use serde_json::{json, Value};
+
 use crate::model::{point::Point4D, yaw::YawSetPoint};
 
+//Number of decimals kept for times and angles in the yaw control JSON, as Skybrush Studio does
+const YAW_JSON_DECIMALS: i32 = 3;
+
 type SkyCvV0PointFormat = [f32;4];
 pub enum DataFormat {
     SkyCvV0([f32; 4]),
@@ -64,13 +69,198 @@ impl CvDataFormat {
     }
 }
 
+/**
+ * Yaw control of a drone in the Skybrush format
+ */
 pub struct YawListDataFormat {
     setpoints: Vec<YawSetPoint>,
-    version: u8
+    version: u8,
+    //Whether the drone turns automatically towards its direction of travel
+    auto_yaw: bool,
+    //Angle added to the automatic yaw, in degrees
+    auto_yaw_offset: f32
 }
 
 impl YawListDataFormat {
     pub fn new(setpoints: &Vec<YawSetPoint>, version: u8) -> Self {
-        Self { setpoints: setpoints.clone(), version }
+        Self { setpoints: setpoints.clone(), version, auto_yaw: false, auto_yaw_offset: 0.0 }
+    }
+
+    pub fn new_from(setpoints: &[YawSetPoint], version: u8, auto_yaw: bool, auto_yaw_offset: f32) -> Self {
+        Self { setpoints: setpoints.to_vec(), version, auto_yaw, auto_yaw_offset }
+    }
+
+    pub fn get_setpoints(&self) -> &[YawSetPoint] {
+        &self.setpoints
+    }
+
+    pub fn get_version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn is_auto_yaw(&self) -> bool {
+        self.auto_yaw
+    }
+
+    pub fn get_auto_yaw_offset(&self) -> f32 {
+        self.auto_yaw_offset
+    }
+
+    /**
+     * Serializes the yaw control to the JSON structure of Skybrush, with setpoints written as
+     * [t, angle] pairs. Times and angles are rounded to 3 decimals.
+     *
+     * Returns:
+     *     an error if a time, an angle or the auto yaw offset is not finite, as JSON has no
+     *     representation for them and they would be written as null
+     */
+    pub fn as_json(&self) -> Result<String, &'static str> {
+        let is_finite = self.auto_yaw_offset.is_finite() && self.setpoints.iter()
+            .all(|setpoint| setpoint.get_time().is_finite() && setpoint.get_angle().is_finite());
+        if !is_finite { return Err("YawListDataFormat.as_json: Times, angles and the auto yaw offset must be finite") }
+
+        let setpoints: Vec<Value> = self.setpoints.iter()
+            .map(|setpoint| json!([round_decimals(setpoint.get_time()), round_decimals(setpoint.get_angle())]))
+            .collect();
+
+        Ok(json!({
+            "version": self.version,
+            "setpoints": setpoints,
+            "autoYaw": self.auto_yaw,
+            "autoYawOffset": round_decimals(self.auto_yaw_offset)
+        }).to_string())
+    }
+
+    /**
+     * Parses the JSON structure written by as_json(). The version defaults to 1, auto yaw to
+     * false and its offset to 0 when missing; the setpoints are required.
+     */
+    pub fn from_json(text: &str) -> Result<Self, &'static str> {
+        let value: Value = serde_json::from_str(text).map_err(|_| "YawListDataFormat.from_json: Invalid JSON")?;
+        let object = value.as_object().ok_or("YawListDataFormat.from_json: The yaw control must be a JSON object")?;
+
+        let version = match object.get("version") {
+            Some(version) => version.as_u64()
+                .and_then(|version| u8::try_from(version).ok())
+                .ok_or("YawListDataFormat.from_json: Invalid version")?,
+            None => 1
+        };
+        let auto_yaw = match object.get("autoYaw") {
+            Some(auto_yaw) => auto_yaw.as_bool().ok_or("YawListDataFormat.from_json: autoYaw must be a boolean")?,
+            None => false
+        };
+        let auto_yaw_offset = match object.get("autoYawOffset") {
+            Some(offset) => offset.as_f64().ok_or("YawListDataFormat.from_json: autoYawOffset must be a number")? as f32,
+            None => 0.0
+        };
+
+        let setpoints = object.get("setpoints")
+            .and_then(|setpoints| setpoints.as_array())
+            .ok_or("YawListDataFormat.from_json: The setpoints are missing")?
+            .iter()
+            .map(|setpoint| match setpoint.as_array().map(|pair| pair.as_slice()) {
+                Some([t, angle]) => match (t.as_f64(), angle.as_f64()) {
+                    (Some(t), Some(angle)) => Ok(YawSetPoint::new(t as f32, angle as f32)),
+                    _ => Err("YawListDataFormat.from_json: Setpoint values must be numbers")
+                },
+                _ => Err("YawListDataFormat.from_json: Each setpoint must be a [t, angle] pair")
+            })
+            .collect::<Result<Vec<YawSetPoint>, &'static str>>()?;
+
+        Ok(Self { setpoints, version, auto_yaw, auto_yaw_offset })
+    }
+}
+
+fn round_decimals(value: f32) -> f64 {
+    let factor = 10f64.powi(YAW_JSON_DECIMALS);
+    (value as f64 * factor).round() / factor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_same(a: &YawListDataFormat, b: &YawListDataFormat) {
+        assert_eq!(a.get_version(), b.get_version());
+        assert_eq!(a.is_auto_yaw(), b.is_auto_yaw());
+        assert_eq!(a.get_auto_yaw_offset(), b.get_auto_yaw_offset());
+        assert_eq!(a.get_setpoints().len(), b.get_setpoints().len());
+        for (a, b) in a.get_setpoints().iter().zip(b.get_setpoints()) {
+            assert_eq!(a.get_time(), b.get_time());
+            assert_eq!(a.get_angle(), b.get_angle());
+        }
+    }
+
+    #[test]
+    fn yaw_json_round_trip() {
+        let setpoints = vec![YawSetPoint::new(0.0, 0.0), YawSetPoint::new(1.5, 90.25), YawSetPoint::new(4.0, -370.5)];
+        let data = YawListDataFormat::new(&setpoints, 1);
+
+        assert_same(&YawListDataFormat::from_json(&data.as_json().unwrap()).unwrap(), &data);
+    }
+
+    #[test]
+    fn yaw_json_round_trip_with_auto_yaw() {
+        let data = YawListDataFormat::new_from(&[YawSetPoint::new(2.0, 45.0)], 1, true, -12.5);
+
+        assert_same(&YawListDataFormat::from_json(&data.as_json().unwrap()).unwrap(), &data);
+    }
+
+    #[test]
+    fn yaw_json_round_trip_of_empty_list() {
+        let data = YawListDataFormat::new(&Vec::new(), 1);
+
+        assert_same(&YawListDataFormat::from_json(&data.as_json().unwrap()).unwrap(), &data);
+    }
+
+    #[test]
+    fn yaw_json_rounds_to_three_decimals() {
+        let data = YawListDataFormat::new(&vec![YawSetPoint::new(0.12345, 10.98765)], 1);
+        let parsed = YawListDataFormat::from_json(&data.as_json().unwrap()).unwrap();
+
+        assert_eq!(parsed.get_setpoints()[0].get_time(), 0.123);
+        assert_eq!(parsed.get_setpoints()[0].get_angle(), 10.988);
+        assert_same(&YawListDataFormat::from_json(&parsed.as_json().unwrap()).unwrap(), &parsed);
+    }
+
+    #[test]
+    fn yaw_json_structure() {
+        let data = YawListDataFormat::new_from(&[YawSetPoint::new(1.0, 90.0)], 1, false, 0.0);
+        let value: Value = serde_json::from_str(&data.as_json().unwrap()).unwrap();
+
+        assert_eq!(value, json!({ "version": 1, "setpoints": [[1.0, 90.0]], "autoYaw": false, "autoYawOffset": 0.0 }));
+    }
+
+    #[test]
+    fn yaw_json_defaults() {
+        let parsed = YawListDataFormat::from_json(r#"{"setpoints": [[0, 10], [2, 20.5]]}"#).unwrap();
+
+        assert_eq!(parsed.get_version(), 1);
+        assert!(!parsed.is_auto_yaw());
+        assert_eq!(parsed.get_auto_yaw_offset(), 0.0);
+        assert_eq!(parsed.get_setpoints()[1].get_angle(), 20.5);
+    }
+
+    #[test]
+    fn yaw_json_rejects_invalid_input() {
+        assert!(YawListDataFormat::from_json("not json").is_err());
+        assert!(YawListDataFormat::from_json("[]").is_err());
+        assert!(YawListDataFormat::from_json(r#"{"version": 1}"#).is_err());
+        assert!(YawListDataFormat::from_json(r#"{"setpoints": [[0, 10, 3]]}"#).is_err());
+        assert!(YawListDataFormat::from_json(r#"{"setpoints": [["0", 10]]}"#).is_err());
+        assert!(YawListDataFormat::from_json(r#"{"setpoints": [], "autoYaw": 1}"#).is_err());
+    }
+
+    #[test]
+    fn yaw_json_rejects_non_finite_values() {
+        for value in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            assert!(YawListDataFormat::new(&vec![YawSetPoint::new(value, 0.0)], 1).as_json().is_err());
+            assert!(YawListDataFormat::new(&vec![YawSetPoint::new(0.0, value)], 1).as_json().is_err());
+            assert!(YawListDataFormat::new_from(&[], 1, true, value).as_json().is_err());
+        }
+
+        //JSON has no literal for them, so they can't be read back either
+        assert!(YawListDataFormat::from_json(r#"{"setpoints": [[null, 10]]}"#).is_err());
+        assert!(YawListDataFormat::from_json(r#"{"setpoints": [[0, NaN]]}"#).is_err());
     }
 }
\ No newline at end of file
@@ -1,4 +1,21 @@
 pub mod data_format;
+pub mod random;
+
+/**
+ * Returns the number of frames from start to end at the given frame rate, i.e. one at start
+ * and one every 1 / fps seconds up to end, or 0 if the frame rate is not positive or end is
+ * before start.
+ */
+pub fn frame_count(start: f32, end: f32, fps: f32) -> usize {
+    if fps <= 0.0 || end < start { return 0 }
+
+    ((end - start) * fps).floor() as usize + 1
+}
+
+/** Returns the time of every frame counted by frame_count(), in seconds */
+pub fn frame_times(start: f32, end: f32, fps: f32) -> Vec<f32> {
+    (0..frame_count(start, end, fps)).map(|index| start + index as f32 / fps).collect()
+}
 
 /**
  * Simplifies a sequence of points to a similar sequence with fewer points, using a disntance function
@@ -18,7 +35,7 @@ pub mod data_format;
  *    the simplified sequence of points. This will be of the same class as the input sequence. It is assumed
  *    that an instance of the sequence may be constructed from a list of items.
  */
-pub fn simplify_path<T: Copy> (points: Vec<T>, eps: f32, distance_func: fn(&Vec<T>, &T, &T) -> Vec<f32>) -> Vec<T> {
+pub fn simplify_path<T: Copy> (points: Vec<T>, eps: f32, distance_func: impl Fn(&Vec<T>, &T, &T) -> Vec<f32>) -> Vec<T> {
     
     let vec:Vec<T> = Vec::new();
 
@@ -26,37 +43,38 @@ pub fn simplify_path<T: Copy> (points: Vec<T>, eps: f32, distance_func: fn(&Vec<
     if points.len() == 0 {
         return vec
     } else {
-        return simplify_line(&points, eps, distance_func)
+        return simplify_line(&points, eps, &distance_func)
     }
 }
 
-fn simplify_line<T: Copy>(points: &Vec<T>, eps: f32, distance_func: fn(&Vec<T>, &T, &T)-> Vec<f32>) -> Vec<T> {
-    let vec: Vec<T> = Vec::new();
-    if points.len() == 0 { return vec }
+fn simplify_line<T: Copy>(points: &Vec<T>, eps: f32, distance_func: &impl Fn(&Vec<T>, &T, &T)-> Vec<f32>) -> Vec<T> {
+    if points.len() <= 2 { return points.clone() }
 
     let start = *points.first().unwrap();
     let end = *points.last().unwrap();
     let dist = distance_func(points, &start, &end);
-    let index = get_max_value_position(&dist);
+    //Only interior points can split the line, otherwise the recursion would never end
+    let index = 1 + get_max_value_position(&dist[1..dist.len() - 1]);
     let dmax = dist[index];
     if dmax <= eps {
         return vec![start, end];
     } else {
         let pre = simplify_line(&points[0..(index + 1)].to_vec(), eps, distance_func);
         let post = simplify_line(&points[index..points.len()].to_vec(), eps, distance_func);
-        return [pre, post].concat()
+        //The point at the split index is both the last point of pre and the first one of post
+        return [&pre[..pre.len() - 1], &post[..]].concat()
     }
 }
 
-fn get_max_value_position(points: &Vec<f32>) -> usize {
+fn get_max_value_position(points: &[f32]) -> usize {
     let mut index: usize = 0;
     let mut max: f32 = points[0];
 
-    for item in points {
+    for (position, item) in points.iter().enumerate() {
         if *item > max {
             max = *item;
+            index = position;
         }
-        index += 1;
     }
 
     index